@@ -0,0 +1,385 @@
+use crate::cli::{Craft, DEFAULT_BIN_NAME};
+use crate::errors::{Error, Result};
+use crate::helpers::{
+    absolute_path, existing_directory, existing_file, valid_crate_name, valid_error_type,
+    valid_package_name, valid_subcommand_name, valid_workspace_root,
+};
+use crate::plan::{PlanFormat, VerifyStep};
+use crate::runner::{CommandRunner, Runner};
+use crate::sink::{FileSink, Sink};
+use crate::templates::ErrorStyle;
+use crate::Dependency;
+use clap::{CommandFactory, FromArgMatches};
+use iocore::Path;
+use std::fmt::Display;
+
+/// `CraftBuilder` builds a [`Craft`] without going through the
+/// command-line. Setters validate their input the same way the
+/// command-line does and [`CraftBuilder::build`] reports every
+/// invalid input at once.
+///
+/// ```
+/// use cargo_craft::CraftBuilder;
+///
+/// let craft = CraftBuilder::new("/tmp/bootstrapped")
+///     .cli(true)
+///     .subcommands(["init", "sync"])
+///     .dependency("regex")
+///     .build()
+///     .unwrap();
+/// assert_eq!(craft.subcommand_names, vec!["init", "sync"]);
+///
+/// // package name, subcommand name and missing `cli'
+/// let errors = CraftBuilder::new("/tmp/bootstrapped")
+///     .package_name("Not-A-Package")
+///     .subcommands(["Not A Subcommand"])
+///     .build()
+///     .unwrap_err();
+/// assert_eq!(errors.to_string().lines().count(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CraftBuilder {
+    craft: Craft,
+    errors: Vec<String>,
+}
+
+impl CraftBuilder {
+    /// `new` starts from the command-line defaults of a crate at `at`
+    pub fn new(at: impl Display) -> CraftBuilder {
+        // neither the environment nor the current directory take part in the defaults
+        let matches = Craft::command()
+            .mut_arg("at", |arg| {
+                arg.value_parser(|val: &str| Ok::<Path, String>(Path::raw(val)))
+            })
+            .mut_arg("templates", |arg| arg.env(None))
+            .mut_arg("pack", |arg| arg.env(None))
+            .get_matches_from(["cargo-craft", "craft"]);
+        let craft = Craft::from_arg_matches(&matches).expect("command-line defaults");
+        CraftBuilder {
+            craft,
+            errors: Vec::new(),
+        }
+        .at(at)
+    }
+    fn validate<T>(&mut self, result: std::result::Result<T, String>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.errors.push(error);
+                None
+            }
+        }
+    }
+    pub fn at(mut self, at: impl Display) -> CraftBuilder {
+        if let Some(at) = self.validate(absolute_path(&at.to_string())) {
+            self.craft.at = at;
+        }
+        self
+    }
+    pub fn package_name(mut self, name: impl Display) -> CraftBuilder {
+        self.craft.package_name = self.validate(valid_package_name(&name.to_string()));
+        self
+    }
+    pub fn version(mut self, version: impl Display) -> CraftBuilder {
+        self.craft.version = version.to_string();
+        self
+    }
+    pub fn description(mut self, description: impl Display) -> CraftBuilder {
+        self.craft.description = Some(description.to_string());
+        self
+    }
+    /// `dependency` takes the same specification as `--dep`, e.g.: `"reqwest -F blocking"`
+    pub fn dependency(mut self, dependency: impl Display) -> CraftBuilder {
+        let dependency = dependency.to_string();
+        match Dependency::parse(&dependency) {
+            Ok(_) => self.craft.dep.push(dependency),
+            Err(error) => self.errors.push(format!("invalid dependency {dependency:#?}: {error}")),
+        }
+        self
+    }
+    pub fn cli(mut self, cli: bool) -> CraftBuilder {
+        self.craft.cli = cli;
+        self
+    }
+    pub fn cli_barebones(mut self, cli_barebones: bool) -> CraftBuilder {
+        self.craft.cli_barebones = cli_barebones;
+        self
+    }
+    pub fn main(mut self, main: bool) -> CraftBuilder {
+        self.craft.main = main;
+        self
+    }
+    pub fn value_enum(mut self, value_enum: bool) -> CraftBuilder {
+        self.craft.value_enum = value_enum;
+        self
+    }
+    pub fn default_bin_name(mut self, name: impl Display) -> CraftBuilder {
+        self.craft.default_bin_name = name.to_string();
+        self
+    }
+    pub fn bin(mut self, name: impl Display) -> CraftBuilder {
+        if let Some(name) = self.validate(valid_crate_name(&name.to_string())) {
+            self.craft.bin.push(name);
+        }
+        self
+    }
+    pub fn lib_path(mut self, path: impl Display) -> CraftBuilder {
+        self.craft.lib_path = Some(path.to_string());
+        self
+    }
+    pub fn bin_path(mut self, path: impl Display) -> CraftBuilder {
+        self.craft.bin_path = path.to_string();
+        self
+    }
+    /// `subcommands` enables subcommands and adds `names` to them
+    pub fn subcommands<T: Display>(mut self, names: impl IntoIterator<Item = T>) -> CraftBuilder {
+        self.craft.subcommands = true;
+        for name in names {
+            if let Some(name) = self.validate(valid_subcommand_name(&name.to_string())) {
+                self.craft.subcommand_names.push(name);
+            }
+        }
+        self
+    }
+    /// `error_type` takes the same specification as `--add-error-type`,
+    /// e.g.: `"Input=std::num::ParseIntError@dataerr"`
+    pub fn error_type(mut self, name: impl Display) -> CraftBuilder {
+        if let Some(name) = self.validate(valid_error_type(&name.to_string())) {
+            self.craft.add_error_type.push(name);
+        }
+        self
+    }
+    pub fn error_style(mut self, style: ErrorStyle) -> CraftBuilder {
+        self.craft.error_style = style;
+        self
+    }
+    pub fn error_sources(mut self, error_sources: bool) -> CraftBuilder {
+        self.craft.error_sources = error_sources;
+        self
+    }
+    pub fn workspace<T: Display>(mut self, members: impl IntoIterator<Item = T>) -> CraftBuilder {
+        self.craft.workspace = true;
+        for member in members {
+            if let Some(member) = self.validate(valid_crate_name(&member.to_string())) {
+                self.craft.members.push(member);
+            }
+        }
+        self
+    }
+    pub fn into_workspace(mut self, root: impl Display) -> CraftBuilder {
+        self.craft.into_workspace = self.validate(valid_workspace_root(&root.to_string()));
+        self
+    }
+    pub fn templates(mut self, directory: impl Display) -> CraftBuilder {
+        self.craft.templates = self.validate(existing_directory(&directory.to_string()));
+        self
+    }
+    pub fn pack(mut self, directory: impl Display) -> CraftBuilder {
+        self.craft.pack = self.validate(existing_directory(&directory.to_string()));
+        self
+    }
+    pub fn verify(mut self, steps: impl IntoIterator<Item = VerifyStep>) -> CraftBuilder {
+        self.craft.verify = steps.into_iter().collect();
+        self
+    }
+    pub fn dry_run(mut self, format: PlanFormat) -> CraftBuilder {
+        self.craft.dry_run = Some(format);
+        self
+    }
+    pub fn force(mut self, force: bool) -> CraftBuilder {
+        self.craft.force = force;
+        self
+    }
+    pub fn rollback(mut self, rollback: bool) -> CraftBuilder {
+        self.craft.rollback = rollback;
+        self
+    }
+    pub fn offline(mut self, offline: bool) -> CraftBuilder {
+        self.craft.offline = offline;
+        self
+    }
+    pub fn no_iocore(mut self, no_iocore: bool) -> CraftBuilder {
+        self.craft.no_iocore = no_iocore;
+        self
+    }
+    pub fn no_serde(mut self, no_serde: bool) -> CraftBuilder {
+        self.craft.no_serde = no_serde;
+        self
+    }
+    pub fn manifest_deps(mut self, manifest_deps: bool) -> CraftBuilder {
+        self.craft.manifest_deps = manifest_deps;
+        self
+    }
+    pub fn lockfile(mut self, lockfile: impl Display) -> CraftBuilder {
+        self.craft.lockfile = self.validate(existing_file(&lockfile.to_string()));
+        self
+    }
+    pub fn cargo_add_fallback(mut self, cargo_add_fallback: bool) -> CraftBuilder {
+        self.craft.cargo_add_fallback = cargo_add_fallback;
+        self
+    }
+    pub fn craft_home(mut self, directory: impl Display) -> CraftBuilder {
+        self.craft.craft_home = self.validate(absolute_path(&directory.to_string()));
+        self
+    }
+    pub fn no_history(mut self, no_history: bool) -> CraftBuilder {
+        self.craft.no_history = no_history;
+        self
+    }
+    pub fn no_receipt(mut self, no_receipt: bool) -> CraftBuilder {
+        self.craft.no_receipt = no_receipt;
+        self
+    }
+    pub fn quiet_add(mut self, quiet_add: bool) -> CraftBuilder {
+        self.craft.quiet_add = quiet_add;
+        self
+    }
+    pub fn verbose(mut self, verbose: bool) -> CraftBuilder {
+        self.craft.verbose = verbose;
+        self
+    }
+    pub fn silent(mut self, silent: bool) -> CraftBuilder {
+        self.craft.silent = silent;
+        self
+    }
+    pub fn script(mut self, script: bool) -> CraftBuilder {
+        self.craft.script = script;
+        self
+    }
+    pub fn runner(mut self, runner: impl CommandRunner + 'static) -> CraftBuilder {
+        self.craft.runner = Runner::new(runner);
+        self
+    }
+    pub fn sink(mut self, sink: impl FileSink + 'static) -> CraftBuilder {
+        self.craft.sink = Sink::new(sink);
+        self
+    }
+    /// `build` returns the [`Craft`] or a [`Error::ParseError`]
+    /// listing every invalid input, one per line
+    pub fn build(self) -> Result<Craft> {
+        let CraftBuilder { craft, mut errors } = self;
+        for (conflicts, message) in [
+            (
+                craft.workspace && craft.package_name.is_some(),
+                "a workspace cannot have a package name",
+            ),
+            (
+                craft.workspace && craft.into_workspace.is_some(),
+                "a workspace cannot be added into another workspace",
+            ),
+            (
+                craft.cli_barebones && !craft.cli,
+                "bare command-line crates require `cli'",
+            ),
+            (
+                craft.cli_barebones && craft.pack.is_some(),
+                "template packs cannot render bare command-line crates",
+            ),
+            (
+                craft.cli_barebones && (craft.subcommands || !craft.subcommand_names.is_empty()),
+                "bare command-line crates cannot have subcommands",
+            ),
+            (
+                craft.cli_barebones && craft.value_enum,
+                "bare command-line crates cannot have `value_enum'",
+            ),
+            (
+                craft.cli_barebones && !craft.add_error_type.is_empty(),
+                "bare command-line crates cannot have error types",
+            ),
+            (
+                craft.cli_barebones
+                    && (!craft.bin.is_empty() || craft.default_bin_name != DEFAULT_BIN_NAME),
+                "bare command-line crates cannot have binaries besides `main.rs'",
+            ),
+            (
+                craft.main && craft.default_bin_name != DEFAULT_BIN_NAME,
+                "`main' crates cannot have a default binary name",
+            ),
+            (
+                craft.main && craft.lib_path.is_some(),
+                "`main' crates cannot have a `lib_path'",
+            ),
+            (
+                craft.main && craft.bin_path != ".",
+                "`main' crates cannot have a `bin_path'",
+            ),
+            (
+                craft.subcommands && !craft.cli,
+                "subcommands require `cli'",
+            ),
+            (
+                craft.error_sources && craft.error_style != ErrorStyle::Handrolled,
+                "`error_sources' requires the handrolled error style",
+            ),
+            (
+                !craft.manifest_deps && (craft.lockfile.is_some() || craft.cargo_add_fallback),
+                "`lockfile' and `cargo_add_fallback' require `manifest_deps'",
+            ),
+        ] {
+            if conflicts {
+                errors.push(message.to_string());
+            }
+        }
+        if errors.is_empty() {
+            Ok(craft)
+        } else {
+            Err(Error::ParseError(errors.join("\n")))
+        }
+    }
+}
+
+impl Craft {
+    pub fn builder(at: impl Display) -> CraftBuilder {
+        CraftBuilder::new(at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn errors(builder: CraftBuilder) -> Vec<String> {
+        match builder.build() {
+            Ok(_) => Vec::new(),
+            Err(error) => error.to_string().lines().map(String::from).collect(),
+        }
+    }
+    #[test]
+    fn test_build_validates_like_the_command_line() {
+        let builder = || Craft::builder("/tmp/built").cli(true);
+        assert_eq!(
+            errors(builder().error_type("Input=std::num::ParseIntError@dataerr")),
+            Vec::<String>::new()
+        );
+        assert_eq!(errors(builder().error_type("=toml::de::Error")).len(), 1);
+        assert_eq!(errors(builder().error_type("Input@nope")).len(), 1);
+
+        let bare = || builder().cli_barebones(true);
+        assert_eq!(errors(bare()), Vec::<String>::new());
+        assert_eq!(errors(bare().subcommands(["init"])).len(), 1);
+        assert_eq!(errors(bare().value_enum(true)).len(), 1);
+        assert_eq!(errors(bare().error_type("Input")).len(), 1);
+        assert_eq!(errors(bare().bin("tool")).len(), 1);
+        assert_eq!(errors(bare().default_bin_name("tool")).len(), 1);
+        assert_eq!(
+            errors(bare().value_enum(true).error_type("Input").bin("tool")).len(),
+            3
+        );
+
+        let main = || builder().main(true);
+        assert_eq!(errors(main()), Vec::<String>::new());
+        assert_eq!(errors(main().default_bin_name("tool")).len(), 1);
+        assert_eq!(errors(main().lib_path("lib")).len(), 1);
+        assert_eq!(errors(main().bin_path("bin")).len(), 1);
+        for args in [["-D", "tool"], ["--lib-path", "lib"], ["--bin-path", "bin"]] {
+            assert!(Craft::try_parse_from(
+                ["cargo-craft", "/tmp/built", "--main"]
+                    .into_iter()
+                    .chain(args)
+            )
+            .is_err());
+        }
+    }
+}
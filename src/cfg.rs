@@ -0,0 +1,203 @@
+use std::fmt::{Display, Formatter};
+
+/// `Cfg` is a `cfg(...)` expression as accepted by
+/// `[target.'cfg(...)'.dependencies]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cfg {
+    Name(String),
+    KeyValue(String, String),
+    All(Vec<Cfg>),
+    Any(Vec<Cfg>),
+    Not(Box<Cfg>),
+}
+
+impl Cfg {
+    /// `parse` parses `cfg(<predicate>)`, e.g.:
+    /// `cfg(all(unix, not(target_os = "macos")))`
+    pub fn parse(val: &str) -> ::std::result::Result<Cfg, String> {
+        let mut parser = CfgParser {
+            tokens: tokenize(val)?,
+            position: 0,
+        };
+        parser.expect(&Token::Ident("cfg".to_string()))?;
+        parser.expect(&Token::Open)?;
+        let cfg = parser.predicate()?;
+        parser.expect(&Token::Close)?;
+        match parser.next() {
+            None => Ok(cfg),
+            Some(token) => Err(format!("unexpected {token} after the end of the expression")),
+        }
+    }
+}
+
+impl Display for Cfg {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        fn list(cfgs: &[Cfg]) -> String {
+            cfgs.iter()
+                .map(|cfg| cfg.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        }
+        match self {
+            Cfg::Name(name) => write!(f, "{name}"),
+            Cfg::KeyValue(key, value) => write!(f, "{key} = {value:?}"),
+            Cfg::All(cfgs) => write!(f, "all({})", list(cfgs)),
+            Cfg::Any(cfgs) => write!(f, "any({})", list(cfgs)),
+            Cfg::Not(cfg) => write!(f, "not({cfg})"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Open,
+    Close,
+    Comma,
+    Equals,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "`{ident}'"),
+            Token::Str(string) => write!(f, "{string:?}"),
+            Token::Open => write!(f, "`('"),
+            Token::Close => write!(f, "`)'"),
+            Token::Comma => write!(f, "`,'"),
+            Token::Equals => write!(f, "`='"),
+        }
+    }
+}
+
+fn tokenize(val: &str) -> ::std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::<Token>::new();
+    let mut chars = val.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            ',' => tokens.push(Token::Comma),
+            '=' => tokens.push(Token::Equals),
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => string.push(c),
+                        None => return Err(format!("unterminated string in {val:#?}")),
+                    }
+                }
+                tokens.push(Token::Str(string));
+            }
+            c if c.is_whitespace() => {}
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            c => return Err(format!("unexpected character {c:?} in {val:#?}")),
+        }
+    }
+    Ok(tokens)
+}
+
+struct CfgParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl CfgParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+    fn expect(&mut self, expected: &Token) -> ::std::result::Result<(), String> {
+        match self.next() {
+            Some(token) if token == *expected => Ok(()),
+            Some(token) => Err(format!("expected {expected} but found {token}")),
+            None => Err(format!("expected {expected} but the expression ended")),
+        }
+    }
+    fn predicate(&mut self) -> ::std::result::Result<Cfg, String> {
+        let name = match self.next() {
+            Some(Token::Ident(name)) => name,
+            Some(token) => return Err(format!("expected a predicate but found {token}")),
+            None => return Err("expected a predicate but the expression ended".to_string()),
+        };
+        match (name.as_str(), self.peek()) {
+            ("all" | "any" | "not", Some(Token::Open)) => {
+                self.next();
+                let mut cfgs = Vec::<Cfg>::new();
+                while self.peek() != Some(&Token::Close) {
+                    cfgs.push(self.predicate()?);
+                    if self.peek() == Some(&Token::Comma) {
+                        self.next();
+                    } else {
+                        break;
+                    }
+                }
+                self.expect(&Token::Close)?;
+                Ok(match name.as_str() {
+                    "all" => Cfg::All(cfgs),
+                    "any" => Cfg::Any(cfgs),
+                    _ if cfgs.len() == 1 => Cfg::Not(Box::new(cfgs.remove(0))),
+                    _ => return Err("`not' takes exactly one predicate".to_string()),
+                })
+            }
+            (_, Some(Token::Equals)) => {
+                self.next();
+                match self.next() {
+                    Some(Token::Str(value)) => Ok(Cfg::KeyValue(name, value)),
+                    Some(token) => Err(format!("expected a string after `{name} =' but found {token}")),
+                    None => Err(format!("expected a string after `{name} ='")),
+                }
+            }
+            _ => Ok(Cfg::Name(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cfg() {
+        assert_eq!(Cfg::parse("cfg(unix)"), Ok(Cfg::Name("unix".to_string())));
+        assert_eq!(
+            Cfg::parse(r#"cfg(all(unix, not(target_os = "macos"),))"#),
+            Ok(Cfg::All(vec![
+                Cfg::Name("unix".to_string()),
+                Cfg::Not(Box::new(Cfg::KeyValue(
+                    "target_os".to_string(),
+                    "macos".to_string()
+                ))),
+            ]))
+        );
+        assert_eq!(
+            Cfg::parse(r#"cfg(any(windows,target_arch="wasm32"))"#)
+                .unwrap()
+                .to_string(),
+            r#"any(windows, target_arch = "wasm32")"#
+        );
+        for invalid in [
+            "unix",
+            "cfg(unix",
+            "cfg(unix))",
+            "cfg(not(unix, windows))",
+            "cfg(target_os = macos)",
+            r#"cfg(target_os = "linux)"#,
+            "cfg(unix; windows)",
+        ] {
+            assert!(Cfg::parse(invalid).is_err(), "{invalid:#?} should not parse");
+        }
+    }
+}
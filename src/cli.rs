@@ -1,24 +1,41 @@
 use crate::errors::{Error, ExecutionResult, Result};
 use crate::helpers::{
-    absolute_path, crate_name_from_path, extend_table, into_acceptable_error_type_name,
-    package_name_from_string_or_path, path_to_entry_path, struct_name_from_package_name,
-    to_pascal_case, valid_manifest_path, valid_package_name, valid_subcommand_name,
+    absolute_path, acceptable_crate_name, crate_name_from_path, default_craft_home,
+    existing_directory, existing_file, extend_table, into_acceptable_error_type_name,
+    package_name_from_string_or_path, path_to_entry_path, relative_path, split_error_type,
+    struct_name_from_package_name, valid_crate_name, valid_error_type, valid_exit_code,
+    valid_package_name, valid_subcommand_name, valid_workspace_root,
 };
-use crate::templates::{render, render_cli, render_info_string};
+use crate::pack::TemplatePack;
+use crate::plan::{Plan, PlanFormat, Step, VerifyStep};
+use crate::receipts::ReceiptStore;
+use crate::resolver::VersionResolver;
+use crate::runner::{CommandRunner, Runner};
+use crate::sink::{FileSink, Sink};
+use crate::templates::{render, render_cli, render_info_string, ErrorStyle};
 use crate::{traceback, Dependency};
 use chrono::{DateTime, Local};
-use clap::CommandFactory;
 use clap::Parser;
 use iocore::Path;
 use serde::{Deserialize, Serialize};
 use std::cmp::PartialEq;
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use toml::{Table, Value};
 
+/// `DEFAULT_BIN_NAME` is the default of `--default-bin-name`, rendered
+/// with the context of the crate
+pub(crate) const DEFAULT_BIN_NAME: &str = "{{ crate_name }}";
+
 #[derive(Parser, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+#[command(
+    after_help = "commands:\n  history   lists the command-lines of previous runs\n  receipts  lists the receipts of previous runs\n  replay    generates a crate again from the receipt of a previous run\n(note: use `./history', `./receipts' or `./replay' to create crates with those names)"
+)]
 pub struct Craft {
     #[arg(
         value_parser = absolute_path,
+        required = false,
+        required_unless_present = "interactive",
         help = "path to new directory containing new crate\n(note: use `--package-name' to define crate name instead of using `<AT>' directory name)"
     )]
     pub at: Path,
@@ -41,7 +58,7 @@ pub struct Craft {
     #[arg(
         short = 'D',
         long,
-        default_value = "{{ crate_name }}",
+        default_value = DEFAULT_BIN_NAME,
         conflicts_with = "main"
     )]
     pub default_bin_name: String,
@@ -87,9 +104,88 @@ pub struct Craft {
     #[arg(short, long)]
     pub offline: bool,
 
-    #[arg(short = 'e', long)]
+    #[arg(
+        long,
+        help = "leaves `iocore' out of the dependencies, generating code that only relies on `std'"
+    )]
+    #[serde(default)]
+    pub no_iocore: bool,
+
+    #[arg(
+        long,
+        help = "leaves `serde' out of the dependencies, generating an `Error' type that is not serializable"
+    )]
+    #[serde(default)]
+    pub no_serde: bool,
+
+    #[arg(
+        long,
+        help = "writes dependencies straight into `Cargo.toml' instead of running `cargo add', taking versions from `--lockfile' or from the local registry"
+    )]
+    #[serde(default)]
+    pub manifest_deps: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_parser = existing_file,
+        requires = "manifest_deps",
+        help = "`Cargo.lock' to take the versions of dependencies from (optional: defaults to the lockfile of `--into-workspace')"
+    )]
+    #[serde(default)]
+    pub lockfile: Option<Path>,
+
+    #[arg(
+        long,
+        requires = "manifest_deps",
+        help = "runs `cargo add' for the dependencies whose version could not be resolved instead of failing"
+    )]
+    #[serde(default)]
+    pub cargo_add_fallback: bool,
+
+    #[arg(
+        short = 'e',
+        long,
+        value_parser = valid_error_type,
+        help = "adds an error variant, optionally converted from an error type with `Name=path::to::Error'"
+    )]
     pub add_error_type: Vec<String>,
 
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ErrorStyle::Handrolled,
+        help = "style of the generated `errors.rs', adding the crate it relies on to the dependencies"
+    )]
+    #[serde(default)]
+    pub error_style: ErrorStyle,
+
+    #[arg(
+        long,
+        help = "generates error variants holding their source error and the location given by `traceback!' (handrolled error style only)"
+    )]
+    #[serde(default)]
+    pub error_sources: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        env = "CARGO_CRAFT_TEMPLATES",
+        value_parser = existing_directory,
+        help = "directory of templates overriding the built-in templates of the same file name (e.g.: `errors.rs.tera', `cli.rs.tera')"
+    )]
+    pub templates: Option<Path>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        env = "CARGO_CRAFT_PACK",
+        value_parser = existing_directory,
+        conflicts_with = "cli_barebones",
+        help = "directory containing a `craft-pack.toml' listing the templates to render instead of the built-in ones"
+    )]
+    pub pack: Option<Path>,
+
     #[arg(
         short = 'R',
         long = "rollback-on-error",
@@ -100,12 +196,97 @@ pub struct Craft {
     #[arg(short, long)]
     pub force: bool,
 
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "human",
+        help = "prints the files that would be written and the commands that would run (`human' or `json') without touching the disk"
+    )]
+    pub dry_run: Option<PlanFormat>,
+
+    #[arg(
+        long,
+        value_name = "STEPS",
+        value_delimiter = ',',
+        default_values = ["check", "build", "test", "doc"],
+        help = "cargo subcommands run against the new crate once generated (`none' to skip verification)"
+    )]
+    pub verify: Vec<VerifyStep>,
+
+    #[arg(
+        long,
+        conflicts_with = "package_name",
+        help = "creates a virtual workspace at <AT> whose member crates are generated with the remaining options"
+    )]
+    pub workspace: bool,
+
+    #[arg(
+        long = "member",
+        value_parser = valid_crate_name,
+        requires = "workspace",
+        value_delimiter = ',',
+        help = "adds a member crate to the workspace (optional: defaults to the directory name of the AT argument), to which every option applies but `--package-name', `--default-bin-name', `--bin', `--subcommand' and `--add-error-type'"
+    )]
+    pub members: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "ROOT",
+        value_parser = valid_workspace_root,
+        conflicts_with = "workspace",
+        help = "adds the new crate at <AT> as a member of the existing workspace at <ROOT>"
+    )]
+    pub into_workspace: Option<Path>,
+
     #[arg(
         long,
         help = "prints the absolute path to the new project directory at the very end of the new project creation so that external scripts can take necessary action"
     )]
     pub script: bool,
 
+    #[arg(
+        long,
+        value_name = "DIR",
+        value_parser = absolute_path,
+        help = "directory holding the history and the receipts of cargo-craft (defaults to `$CARGO_CRAFT_HOME', `$CARGO_HOME' or `~/.cargo')"
+    )]
+    #[serde(default)]
+    pub craft_home: Option<Path>,
+
+    #[arg(long, help = "does not record the command-line in the history of cargo-craft")]
+    #[serde(default)]
+    pub no_history: bool,
+
+    #[arg(long, help = "does not write a receipt of the run")]
+    #[serde(default)]
+    pub no_receipt: bool,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "takes the options of `[preset.<NAME>]' from `craft.toml' and `.craft.toml'"
+    )]
+    #[serde(default)]
+    pub preset: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "preset",
+        help = "ignores the options of `craft.toml' and `.craft.toml'"
+    )]
+    #[serde(default)]
+    pub no_config: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "asks about the options left out of the command-line then confirms the plan before generating the crate"
+    )]
+    #[serde(default)]
+    pub interactive: bool,
+
     #[arg(skip = chrono::Local::now())]
     pub started_at: DateTime<Local>,
 
@@ -114,11 +295,25 @@ pub struct Craft {
 
     #[arg(skip)]
     pub runtime_errors: Vec<Error>,
+
+    #[arg(skip)]
+    pub workspace_root: Option<Path>,
+
+    #[arg(skip)]
+    #[serde(skip)]
+    pub runner: Runner,
+
+    #[arg(skip)]
+    #[serde(skip)]
+    pub sink: Sink,
 }
 pub trait ClapExecuter: Parser + std::fmt::Debug {
     fn run(args: &Self) -> Result<()>;
+    fn parse_args(args: Vec<String>) -> Self {
+        Self::parse_from(args)
+    }
     fn main() -> ExecutionResult<Self> {
-        let args = Self::parse_from(Self::args());
+        let args = Self::parse_args(Self::args());
         match Self::run(&args) {
             Ok(()) => ExecutionResult::Ok(args),
             Err(error) => ExecutionResult::Err(args, error),
@@ -130,30 +325,30 @@ pub trait ClapExecuter: Parser + std::fmt::Debug {
         let execname = Path::new(&argv[0]).name();
         let shift_args =
             (execname == "cargo" || execname == "cargo-craft") && argc > 1 && argv[1] == "craft";
-        let args = if shift_args {
+        if shift_args {
             argv[1..].to_vec()
         } else {
             argv.to_vec()
-        };
-        args
+        }
     }
 }
 impl Craft {
-    pub fn crate_name(&self) -> String {
-        crate_name_from_path(&self.at).unwrap()
+    pub fn crate_name(&self) -> Result<String> {
+        crate_name_from_path(&self.at).map_err(|error| traceback!(ParseError, error))
     }
-    pub fn package_name(&self) -> String {
-        package_name_from_string_or_path(self.package_name.clone(), &self.at).unwrap()
+    pub fn package_name(&self) -> Result<String> {
+        package_name_from_string_or_path(self.package_name.clone(), &self.at)
+            .map_err(|error| traceback!(ParseError, error))
     }
     pub fn subcommand_names(&self) -> Vec<String> {
-        if self.subcommand_names.len() > 0 {
+        if !self.subcommand_names.is_empty() {
             self.subcommand_names.clone()
         } else {
             vec!["hello".to_string()]
         }
     }
-    pub fn struct_name(&self) -> String {
-        struct_name_from_package_name(&self.package_name())
+    pub fn struct_name(&self) -> Result<String> {
+        Ok(struct_name_from_package_name(self.package_name()?))
     }
     pub fn version(&self) -> String {
         self.version.clone()
@@ -170,22 +365,25 @@ impl Craft {
     pub fn single_main_bin(&self) -> bool {
         self.main || self.cli_barebones
     }
-    pub fn lib_path(&self) -> Path {
-        if self.single_main_bin() {
+    pub fn lib_path(&self) -> Result<Path> {
+        Ok(if self.single_main_bin() {
             Path::new("src")
         } else {
-            let lib_path_sanitized =
-                crate_name_from_path(&self.lib_path.clone().unwrap_or_else(|| self.crate_name()))
-                    .expect("lib-path sanitized via crate_name_from_path");
-            Path::new(lib_path_sanitized)
+            let lib_path = match &self.lib_path {
+                Some(lib_path) => lib_path.clone(),
+                None => self.crate_name()?,
+            };
+            Path::new(
+                crate_name_from_path(lib_path).map_err(|error| traceback!(ParseError, error))?,
+            )
         }
-        .relative_to_cwd()
+        .relative_to_cwd())
     }
-    pub fn bin_path(&self) -> Path {
+    pub fn bin_path(&self) -> Result<Path> {
         if self.single_main_bin() {
             self.lib_path()
         } else {
-            self.path_to(&self.bin_path).relative_to_cwd()
+            Ok(Path::new(&self.bin_path))
         }
     }
     pub fn lib_options() -> Table {
@@ -197,25 +395,20 @@ impl Craft {
     }
     pub fn bin_options() -> Table {
         let mut options = Craft::lib_options();
-        for falsy in ["doc"] {
-            options.insert(falsy.to_string(), Value::Boolean(false));
-        }
+        options.insert("doc".to_string(), Value::Boolean(false));
         options
     }
-    pub fn bin_names(&self) -> Vec<String> {
+    pub fn bin_names(&self) -> Result<Vec<String>> {
         let mut binaries = self.bin.clone();
-        if !binaries.contains(&self.crate_name()) || binaries.is_empty() {
-            binaries.push(
-                self.default_bin_name()
-                    .unwrap_or_else(|_| self.crate_name()),
-            );
+        if !binaries.contains(&self.crate_name()?) || binaries.is_empty() {
+            binaries.push(self.default_bin_name()?);
         }
 
-        binaries
+        Ok(binaries)
     }
-    pub fn bin_entries(&self) -> Vec<Table> {
+    pub fn bin_entries(&self) -> Result<Vec<Table>> {
         let mut entries = Vec::<Table>::new();
-        let bin_names = self.bin_names();
+        let bin_names = self.bin_names()?;
         let bin_count = bin_names.len();
         for (index, name) in bin_names.into_iter().enumerate() {
             let is_cargo = name.starts_with("cargo-");
@@ -240,7 +433,7 @@ impl Craft {
             table.insert(
                 "path".to_string(),
                 Value::String(
-                    Path::new(&self.bin_path())
+                    Path::new(self.bin_path()?)
                         .join(bin_rs_filename)
                         .to_string(),
                 ),
@@ -248,94 +441,139 @@ impl Craft {
             table = extend_table(&Craft::bin_options(), &table);
             entries.push(table);
         }
-        entries
+        Ok(entries)
     }
     pub fn git_entries(&self) -> Vec<Table> {
         let mut entries = Vec::<Table>::new();
-        for name in vec![
+        for name in [
             ".gitignore",
             ".rustfmt.toml",
             "rust-toolchain.toml",
             "README.md",
         ] {
+            if self.is_workspace_member() && name != "README.md" {
+                continue;
+            }
             let mut table = Table::new();
             table.insert("name".to_string(), Value::String(name.to_string()));
-            table.insert(
-                "path".to_string(),
-                Value::String(Path::new(&self.project_path()).join(&name).to_string()),
-            );
+            table.insert("path".to_string(), Value::String(name.to_string()));
             entries.push(table);
         }
         entries
     }
-    pub fn lib_entry(&self, path: impl Display) -> Option<Table> {
+    pub fn lib_entry(&self, path: impl Display) -> Result<Option<Table>> {
         let mut entry = Table::new();
-        entry.insert("name".to_string(), Value::String(self.package_name()));
+        entry.insert("name".to_string(), Value::String(self.package_name()?));
         entry.insert(
             "path".to_string(),
-            Value::String(self.lib_path().join(path).to_string()),
+            Value::String(self.lib_path()?.join(path).to_string()),
         );
-        Some(extend_table(&Craft::bin_options(), &entry))
+        Ok(Some(extend_table(&Craft::bin_options(), &entry)))
     }
     pub fn manifest_path(&self) -> Path {
         self.path_to("Cargo.toml")
     }
     pub fn default_bin_name(&self) -> Result<String> {
-        Ok(render_info_string(&self.clone(), &self.default_bin_name)?)
+        render_info_string(&self.clone(), &self.default_bin_name)
     }
     pub fn deps(&self) -> Result<Vec<Dependency>> {
         let mut deps = Vec::new();
-        for h in self.dep.iter() {
-            let mut args = vec![format!("dependency")];
-            args.extend(
-                h.split(" ")
-                    .filter(|h| h.trim().len() > 0)
-                    .map(|h| h.to_string()),
-            );
-            deps.push(Dependency::try_parse_from(args).map_err(|e| traceback!(ParseError, e))?);
+        for spec in self.dep.iter() {
+            let mut dep = Dependency::parse(spec)?;
+            // `--path' is given relative to the current directory whereas
+            // `cargo add' runs within the new crate
+            dep.path = dep.path.map(|path| relative_path(self.path(), path));
+            deps.push(dep);
         }
         Ok(deps)
     }
     pub fn error_types(&self) -> Result<Vec<String>> {
-        let mut error_types_pascal_name = self
-            .deps()?
-            .into_iter()
-            .map(|dep| dep.pascal_name())
-            .collect::<Vec<String>>();
-        error_types_pascal_name.extend(
-            self.add_error_type
-                .clone()
-                .into_iter()
-                .map(|dep| into_acceptable_error_type_name(&dep))
-                .collect::<Vec<String>>(),
-        );
+        let mut error_types_pascal_name = Vec::<String>::new();
+        for (name, _) in self.error_conversions()? {
+            if !error_types_pascal_name.contains(&name) {
+                error_types_pascal_name.push(name);
+            }
+        }
         Ok(error_types_pascal_name)
     }
+    /// `error_conversions` pairs the name of every error variant with
+    /// the error types converted into it, e.g.:
+    /// `("Toml", ["toml::de::Error", "toml::ser::Error"])`
+    pub fn error_conversions(&self) -> Result<Vec<(String, Vec<String>)>> {
+        let explicit = self
+            .add_error_type
+            .iter()
+            .map(|error_type| {
+                let (name, path, _) = split_error_type(error_type);
+                (name, path)
+            })
+            .collect::<Vec<(String, Option<String>)>>();
+        let mut conversions = Vec::<(String, Vec<String>)>::new();
+        for dep in self.deps()? {
+            // `-e Name=path' takes over the conversion of a known error type
+            let paths = dep
+                .error_type_paths()
+                .into_iter()
+                .filter(|path| !explicit.iter().any(|(_, other)| other.as_ref() == Some(path)))
+                .collect();
+            conversions.push((dep.pascal_name(), paths));
+        }
+        for (name, path) in explicit {
+            conversions.push((into_acceptable_error_type_name(&name), path.into_iter().collect()));
+        }
+        Ok(conversions)
+    }
+    /// `error_exit_codes` maps every error variant to its exit code,
+    /// `-e Name@code` taking precedence over the defaults of dependencies
+    pub fn error_exit_codes(&self) -> Result<BTreeMap<String, u8>> {
+        let mut exit_codes = BTreeMap::<String, u8>::new();
+        for dep in self.deps()? {
+            exit_codes.entry(dep.pascal_name()).or_insert(dep.exit_code());
+        }
+        for error_type in self.add_error_type.iter() {
+            let (name, _, exit_code) = split_error_type(error_type);
+            let name = into_acceptable_error_type_name(&name);
+            match exit_code {
+                Some(exit_code) => {
+                    let exit_code =
+                        valid_exit_code(&exit_code).map_err(|error| traceback!(ParseError, error))?;
+                    exit_codes.insert(name, exit_code);
+                }
+                None => {
+                    exit_codes.entry(name).or_insert(70);
+                }
+            }
+        }
+        Ok(exit_codes)
+    }
     pub fn rollback_on_error(&self) -> bool {
-        self.rollback == true
+        self.rollback
     }
     pub fn render_templates(&self) -> Result<Vec<(String, Vec<Option<Table>>)>> {
-        let mut ttargets = if !self.cli_barebones {
+        // the root of a workspace is left out of packs, which render its members
+        if let Some(path) = self.pack.as_ref().filter(|_| !self.workspace) {
+            return TemplatePack::load(path)?.render(self);
+        }
+        let mut ttargets = if self.workspace {
+            Vec::new()
+        } else if !self.cli_barebones {
             vec![
+                (render(self, "lib.rs")?, vec![self.lib_entry("lib.rs")?]),
                 (
-                    render(&self, "lib.rs").unwrap(),
-                    vec![self.lib_entry("lib.rs")],
+                    render(self, "dispatch.rs")?,
+                    vec![self.lib_entry("dispatch.rs")?],
                 ),
                 (
-                    render(&self, "dispatch.rs").unwrap(),
-                    vec![self.lib_entry("dispatch.rs")],
+                    render(self, "{{package_name}}.rs")?,
+                    vec![self.lib_entry(format!("{}.rs", self.package_name()?))?],
                 ),
                 (
-                    render(&self, "{{package_name}}.rs").unwrap(),
-                    vec![self.lib_entry(format!("{}.rs", self.package_name()))],
+                    render(self, self.errors_template_name()?)?,
+                    vec![self.lib_entry("errors.rs")?],
                 ),
                 (
-                    render(&self, "errors.rs").unwrap(),
-                    vec![self.lib_entry("errors.rs")],
-                ),
-                (
-                    render_cli(&self).unwrap(),
-                    self.bin_entries()
+                    render_cli(self)?,
+                    self.bin_entries()?
                         .iter()
                         .map(|entry| Some(entry.clone()))
                         .collect::<Vec<Option<Table>>>(),
@@ -343,122 +581,209 @@ impl Craft {
             ]
         } else {
             vec![
-                (
-                    render(&self, "bare.main.rs").unwrap(),
-                    vec![self.lib_entry("main.rs")],
-                ),
-                (
-                    render(&self, "bare.mod.cli.rs").unwrap(),
-                    vec![self.lib_entry("cli.rs")],
-                ),
+                (render(self, "bare.main.rs")?, vec![self.lib_entry("main.rs")?]),
+                (render(self, "bare.mod.cli.rs")?, vec![self.lib_entry("cli.rs")?]),
             ]
         };
-        let git_entries = self.git_entries().into_iter().map(|entry| {
+        for entry in self.git_entries() {
             let name = entry
                 .get("name")
-                .expect("entry name")
-                .as_str()
-                .expect("str");
-            (render(&self, name).unwrap(), vec![Some(entry.clone())])
-        });
-        ttargets.extend(git_entries);
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            ttargets.push((render(self, &name)?, vec![Some(entry)]));
+        }
         let ttargets = ttargets
-            .iter()
-            .filter(|(path, _)| path.is_some())
-            .map(|(path, entries)| (path.clone().unwrap(), entries.clone()))
+            .into_iter()
+            .filter_map(|(rendered, entries)| Some((rendered?, entries)))
             .collect::<Vec<(String, Vec<Option<Table>>)>>();
         Ok(ttargets)
     }
-    pub fn render_and_write_templates(&self) -> Result<Vec<Path>> {
-        let ttargets = self.render_templates()?;
-        let mut written_paths = Vec::<Path>::new();
-        for (template, target) in ttargets {
+    pub fn template_steps(&self) -> Result<Vec<Step>> {
+        let mut steps = Vec::<Step>::new();
+        for (template, target) in self.render_templates()? {
             for target in target
                 .iter()
                 .filter(|entry| entry.is_some())
-                .map(|entry| path_to_entry_path(entry.clone()))
-                .filter(|path| path.is_some())
-                .map(|path| path.unwrap())
-                .collect::<Vec<Path>>()
+                .filter_map(|entry| path_to_entry_path(entry.clone()))
             {
-                let path = self.path_to(target);
-                match path.write(&template.as_bytes()) {
-                    Ok(path) => {
-                        if !self.silent && self.verbose {
-                            eprintln!("wrote {path}");
-                        }
-                        written_paths.push(path);
-                    }
-                    Err(error) => {
-                        return Err(Error::IOError(format!("error writing {path}: {error}")))
-                    }
-                }
+                steps.push(Step::WriteFile {
+                    path: self.path_to(target),
+                    contents: template.clone(),
+                });
             }
         }
-        Ok(written_paths)
+        Ok(steps)
     }
-    pub fn rustfmt_paths(&self, written_paths: &Vec<Path>) -> Result<()> {
-        for target in written_paths {
-            if target.extension().unwrap_or_default().ends_with("rs") {
-                self.shell_command(format!("rustfmt {}", target.relative_to_cwd()), Path::cwd())?;
-            }
-        }
-        Ok(())
+    pub fn rustfmt_steps(&self, steps: &[Step]) -> Vec<Step> {
+        steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::WriteFile { path, .. }
+                    if path.extension().unwrap_or_default().ends_with("rs") =>
+                {
+                    Some(Step::ShellCommand {
+                        command: format!("rustfmt {}", path.relative_to_cwd()),
+                        current_dir: Path::cwd(),
+                    })
+                }
+                _ => None,
+            })
+            .collect()
     }
     pub fn is_cli(&self) -> bool {
         self.main || self.cli || self.cli_barebones
     }
-    pub fn cargo_add_dependencies(&self) -> Result<()> {
+    /// `baseline_dep_specs` are the dependencies of every generated crate
+    pub fn baseline_dep_specs(&self) -> Vec<&'static str> {
+        let mut specs = Vec::<&str>::new();
         if self.is_cli() {
-            self.cargo_add("clap -F derive,env,string,unicode,wrap_help", self.path())?;
+            specs.push("clap -F derive,env,string,unicode,wrap_help");
+        }
+        if !self.no_iocore {
+            specs.push("iocore");
         }
-        self.cargo_add("iocore", self.path())?;
-        self.cargo_add("serde -F derive", self.path())?;
+        if !self.no_serde {
+            specs.push("serde -F derive");
+        }
+        specs.extend(self.error_style.dependency());
+        specs
+    }
+    pub fn baseline_deps(&self) -> Result<Vec<Dependency>> {
+        self.baseline_dep_specs()
+            .into_iter()
+            .map(Dependency::parse)
+            .collect()
+    }
+    /// `dependency_steps` adds the dependencies either through
+    /// `cargo add` or, with `--manifest-deps`, straight into the manifest
+    pub fn dependency_steps(&self) -> Result<Vec<Step>> {
+        if self.manifest_deps {
+            self.manifest_dependency_steps()
+        } else {
+            self.cargo_add_steps()
+        }
+    }
+    pub fn manifest_dependency_steps(&self) -> Result<Vec<Step>> {
+        let lockfile = self.lockfile.clone().or_else(|| {
+            self.workspace_root()
+                .map(|root| root.join("Cargo.lock"))
+                .filter(|lockfile| lockfile.is_file())
+        });
+        let resolver = VersionResolver::new(lockfile.as_ref(), Some(VersionResolver::registry()))?;
+        let mut steps = Vec::<Step>::new();
+        for dep in self.baseline_deps()?.into_iter().chain(self.deps()?) {
+            let version = match &dep.version {
+                Some(version) => Some(version.clone()),
+                None if dep.path.is_some() || dep.git.is_some() => None,
+                None => match resolver.resolve(&dep.name, None) {
+                    Some(version) => Some(version),
+                    None if self.cargo_add_fallback => {
+                        steps.push(Step::ShellCommand {
+                            command: self.cargo_add_command(&dep),
+                            current_dir: self.path(),
+                        });
+                        continue;
+                    }
+                    None => {
+                        return Err(traceback!(
+                            RuntimeError,
+                            format!(
+                                "could not resolve a version of {:#?} (use `--lockfile', `{}@<version>' or `--cargo-add-fallback')",
+                                dep.name, dep.name
+                            )
+                        ));
+                    }
+                },
+            };
+            steps.push(Step::AddDependency {
+                manifest: self.manifest_path(),
+                target: dep.target.clone(),
+                table: dep.manifest_table().to_string(),
+                name: dep.import_name(),
+                entry: dep.manifest_entry(version.as_deref()).to_string(),
+            });
+        }
+        Ok(steps)
+    }
+    pub fn cargo_add_steps(&self) -> Result<Vec<Step>> {
+        let mut commands = self
+            .baseline_dep_specs()
+            .into_iter()
+            .map(|dep| self.cargo_add_command(dep))
+            .collect::<Vec<String>>();
         for dep in self.deps()? {
-            self.cargo_add(&dep, self.path())?;
+            let command = self.cargo_add_command(&dep);
+            commands.push(if dep.is_local_git() {
+                // cargo refuses to check out git repositories offline, local ones included
+                command.replacen(" --offline", "", 1)
+            } else {
+                command
+            });
         }
-        Ok(())
+        Ok(commands
+            .into_iter()
+            .map(|command| Step::ShellCommand {
+                command,
+                current_dir: self.path(),
+            })
+            .collect())
     }
-    pub fn run_git_ops(&self) -> Result<()> {
-        self.shell_command("git init", self.path())?;
-        self.shell_command("git add .", self.path())?;
-        Ok(())
+    pub fn git_steps(&self) -> Vec<Step> {
+        let mut commands = Vec::<&str>::new();
+        if self.into_workspace.is_none() {
+            commands.push("git init");
+        }
+        commands.push("git add .");
+        commands
+            .into_iter()
+            .map(|command| Step::ShellCommand {
+                command: command.to_string(),
+                current_dir: self.path(),
+            })
+            .collect()
     }
-    pub fn go(&self) -> Result<()> {
-        if self.at.exists() {
-            if self.force {
-                self.at.delete()?;
-            }
+    pub fn cargo_subcommand_steps(&self) -> Vec<Step> {
+        self.verify
+            .iter()
+            .filter_map(VerifyStep::subcommand)
+            .map(|subcommand| Step::CargoSubcommand {
+                subcommand: subcommand.to_string(),
+                command: self.cargo_subcommand_command(subcommand),
+                current_dir: self.path(),
+            })
+            .collect()
+    }
+    /// `plan` lists every file that [`Craft::go`] writes and every
+    /// command it runs without touching the disk
+    pub fn plan(&self) -> Result<Plan> {
+        let mut plan = Plan::default();
+        if self.at.exists() && self.force {
+            plan.push(Step::RemoveDirectory {
+                path: self.at.clone(),
+            });
+        }
+        if self.workspace {
+            plan.extend(self.workspace_steps()?);
+        } else if self.into_workspace.is_some() {
+            plan.extend(self.into_workspace_steps()?);
         } else {
-            let mut command = clap::Command::new("cargo-craft");
-            for arg in Craft::command().get_arguments() {
-                if arg.get_id().as_str() == "at" {
-                    command = command.arg(clap::Arg::new("at").value_parser(valid_manifest_path));
-                } else {
-                    command = command.arg(arg.clone());
-                }
-            }
-            command.get_matches_from(Self::args());
-        };
-        let manifest_path = self.manifest_path();
-        let manifest_string = render(&self, "Cargo.toml").unwrap().unwrap();
-        manifest_path.write(&manifest_string.as_bytes()).unwrap();
-        if !self.silent && self.verbose {
-            eprintln!("wrote {}", manifest_path);
+            plan.extend(self.scaffold_steps()?);
+            plan.extend(self.dependency_steps()?);
         }
-
-        let written_paths = self.render_and_write_templates()?;
-        self.rustfmt_paths(&written_paths)?;
-
-        self.cargo_add_dependencies()?;
-
-        self.run_git_ops()?;
-
-        for subcommand in ["check", "build", "test", "doc"] {
-            self.call_cargo_subcommand(subcommand)?;
+        plan.extend(self.git_steps());
+        plan.extend(self.cargo_subcommand_steps());
+        Ok(plan)
+    }
+    pub fn go(&self) -> Result<()> {
+        self.validate_path()?;
+        let plan = self.plan()?;
+        if let Some(format) = self.dry_run {
+            print!("{}", plan.format(format)?);
+            return Ok(());
         }
+        plan.execute(self)?;
 
-        self.write_receipt()?;
         if self.script {
             // the very last println should be crate name so that external scripts can use that information
             let name = self.at.name();
@@ -466,48 +791,67 @@ impl Craft {
         }
         Ok(())
     }
+    pub fn validate_path(&self) -> Result<()> {
+        if !self.at.exists() {
+            acceptable_crate_name(&self.at.name()).map_err(|error| traceback!(ParseError, error))?;
+        }
+        Ok(())
+    }
+    /// `with_runner` sets the [`CommandRunner`] through which every
+    /// shell command is issued
+    pub fn with_runner(mut self, runner: impl CommandRunner + 'static) -> Craft {
+        self.runner = Runner::new(runner);
+        self
+    }
+    /// `with_sink` sets the [`FileSink`] receiving every generated file
+    pub fn with_sink(mut self, sink: impl FileSink + 'static) -> Craft {
+        self.sink = Sink::new(sink);
+        self
+    }
+    pub fn errors_template_name(&self) -> Result<&'static str> {
+        match (self.error_sources, self.error_style) {
+            (false, style) => Ok(style.template_name()),
+            (true, ErrorStyle::Handrolled) => Ok("errors.sources.rs"),
+            (true, style) => Err(traceback!(
+                ParseError,
+                format!("`--error-sources' does not apply to `--error-style {style}'")
+            )),
+        }
+    }
+    pub fn manifest_template_name(&self) -> &'static str {
+        if self.workspace {
+            "Cargo.workspace.toml"
+        } else {
+            "Cargo.toml"
+        }
+    }
+    pub fn manifest_step(&self) -> Result<Step> {
+        Ok(Step::WriteFile {
+            path: self.manifest_path(),
+            contents: render(self, self.manifest_template_name())?.unwrap_or_default(),
+        })
+    }
+    /// `scaffold_steps` writes the manifest and templates of the
+    /// crate at `self.at` then formats the rust sources among them
+    pub fn scaffold_steps(&self) -> Result<Vec<Step>> {
+        let mut steps = vec![self.manifest_step()?];
+        steps.extend(self.template_steps()?);
+        steps.extend(self.rustfmt_steps(&steps));
+        Ok(steps)
+    }
     pub fn write_receipt(&self) -> Result<()> {
+        if self.no_receipt {
+            return Ok(());
+        }
         let mut receipt = self.clone();
         receipt.at = receipt.path().try_canonicalize();
         receipt.finished_at = Some(Local::now());
-        let path = Craft::receipts_path();
-
-        let (mut receipts, errors) = self.read_receipts(&path).unwrap_or_default();
-        if errors.len() > 0 && receipts.is_empty() && !self.silent && self.verbose {
-            for (location, error) in errors.iter() {
-                eprintln!(
-                    "[{}:{}] WARNING: trying to parse {location} in {path}: {error}",
-                    file!(),
-                    line!()
-                );
-            }
-        }
-        let prevcount = receipts.len();
-        receipts.push(receipt.clone());
-        let curcount = receipts.len();
-
-        if curcount > prevcount {
-            let lines = receipts
-                .into_iter()
-                .map(|receipt| {
-                    serde_json::to_string(&receipt)
-                        .unwrap_or_default()
-                        .trim()
-                        .to_string()
-                })
-                .filter(|line| !line.is_empty())
-                .map(String::from)
-                .collect::<Vec<String>>();
-
-            let wrote_to = path.write(lines.join("\n").as_bytes())?;
-            eprintln!("wrote receipt to: {wrote_to}");
-        }
+        let store = ReceiptStore::new(self.receipts_path());
+        store.append(&receipt)?;
+        eprintln!("wrote receipt to: {}", store.path());
         Ok(())
     }
-    pub fn call_cargo_subcommand<T: Display + for<'a> PartialEq<&'a str>>(
-        &self,
-        subcommand: T,
-    ) -> Result<()> {
+    pub fn cargo_subcommand_command(&self, subcommand: impl Display) -> String {
         let mut cargo_command_args = Vec::<String>::new();
         if self.offline {
             cargo_command_args.push("--offline".to_string());
@@ -515,36 +859,49 @@ impl Craft {
         if self.quiet_add || self.silent {
             cargo_command_args.push("--quiet".to_string());
         }
-        let command = format!("cargo {} {}", subcommand, cargo_command_args.join(" "));
+        format!("cargo {} {}", subcommand, cargo_command_args.join(" "))
+            .trim_end()
+            .to_string()
+    }
+    pub fn call_cargo_subcommand(&self, subcommand: impl Display) -> Result<()> {
+        let command = self.cargo_subcommand_command(&subcommand);
         if !self.silent && self.verbose {
             eprintln!("{command}");
         }
         match self.shell_command(&command, self.path())? {
             0 => Ok(()),
-            exit_code => {
-                let error = format!("{:#?} failed with {}", &command, exit_code);
-                if subcommand == "check" {
-                    return Err(crate::Error::ShellCommandError(error));
-                } else {
-                    eprintln!("command failed: `{command}'");
-                    std::process::exit(1);
-                }
-            }
+            exit_code => Err(crate::Error::ShellCommandError(format!(
+                "{:#?} failed with {}",
+                &command, exit_code
+            ))),
         }
     }
 }
 
 impl ClapExecuter for Craft {
+    fn parse_args(args: Vec<String>) -> Craft {
+        Craft::parse_with_config(&args).unwrap_or_else(|error| error.exit())
+    }
     fn run(args: &Craft) -> Result<()> {
         let mut post_run_stderr = Vec::<String>::new();
         let could_rollback = args.rollback_on_error() && !args.path().try_canonicalize().exists();
-        match write_history() {
-            Ok(history) => {
-                let size = history.len();
-                post_run_stderr.push(format!("{size} entries in history"));
-            }
-            Err(error) => {
-                post_run_stderr.push(format!("failed to write to history: {error}"));
+        let workspace_manifest = args
+            .into_workspace
+            .as_ref()
+            .and_then(|_| args.workspace_manifest_path())
+            .and_then(|path| Some((path.clone(), path.read().ok()?)));
+        if args.dry_run.is_some() {
+            return args.go();
+        }
+        if !args.no_history {
+            match args.write_history() {
+                Ok(history) => {
+                    let size = history.len();
+                    post_run_stderr.push(format!("{size} entries in history"));
+                }
+                Err(error) => {
+                    post_run_stderr.push(format!("failed to write to history: {error}"));
+                }
             }
         }
         let display_post_run_messages = move || {
@@ -558,25 +915,29 @@ impl ClapExecuter for Craft {
         };
         match args.go() {
             Ok(()) => {
+                args.write_receipt()?;
                 if args.verbose {
                     display_post_run_messages();
                 }
                 Ok(())
             }
             Err(error) => {
-                eprintln!("ERROR: {error}");
                 if could_rollback {
                     eprintln!("rolling back {}", args.path());
                     args.path().delete()?;
+                    if let Some((path, manifest)) = &workspace_manifest {
+                        eprintln!("restoring {path}");
+                        path.write(manifest.as_bytes())?;
+                    }
                 }
                 display_post_run_messages();
-                Ok(())
+                Err(error)
             }
         }
     }
 }
 impl Craft {
-    pub fn cargo_add(&self, dep: impl Display, current_dir: Path) -> Result<()> {
+    pub fn cargo_add_command(&self, dep: impl Display) -> String {
         let mut opts = Vec::<String>::new();
         if self.quiet_add {
             opts.push("-q".to_string());
@@ -585,11 +946,7 @@ impl Craft {
             opts.push("--offline".to_string());
         }
         opts.push(dep.to_string());
-
-        let command = format!("cargo add {}", opts.join(" "));
-        // eprintln!("\x1b[1;38;5;34m{command}\x1b[0m");
-        self.shell_command(&command, current_dir)?;
-        Ok(())
+        format!("cargo add {}", opts.join(" "))
     }
     pub fn shell_command(
         &self,
@@ -599,89 +956,60 @@ impl Craft {
         if self.verbose {
             println!("{}", &command.to_string())
         }
-        Ok(iocore::shell_command(command, current_dir)?)
+        self.runner.run(&command.to_string(), &current_dir.into())
     }
 }
 
+pub const RECEIPTS_FILE_NAME: &str = "craft-receipts.ldjson";
+pub const HISTORY_FILE_NAME: &str = "craft-history.txt";
+
 impl Craft {
-    pub fn receipts_path() -> Path {
-        Path::new("~/.cargo/craft-receipts.ldjson").try_canonicalize()
+    /// `craft_home` is either `--craft-home` or [`default_craft_home`]
+    pub fn craft_home(&self) -> Path {
+        self.craft_home.clone().unwrap_or_else(default_craft_home)
     }
-    pub fn read_receipts(
-        &self,
-        receipts_path: &Path,
-    ) -> Result<(Vec<Craft>, Vec<(String, serde_json::Error)>)> {
-        Ok(if receipts_path.is_file() {
-            let mut old_receipts = Vec::<Craft>::new();
-
-            let lines = receipts_path.read_lines()?;
-            let mut errors = Vec::<(String, serde_json::Error)>::new();
-            for (index, line) in lines.iter().enumerate() {
-                let lineno = index + 1;
-                match serde_json::from_str::<Craft>(line.as_str()) {
-                    Ok(old) => old_receipts.push(old),
-                    Err(error) => {
-                        errors.push((format!("json ld from line {lineno}"), error));
-                    }
-                }
-            }
-            if errors.is_empty() {
-                (old_receipts, errors)
-            } else if errors.len() == lines.len() {
-                let all_lines = lines.join("\n");
-                match serde_json::from_str::<Craft>(all_lines.as_str())
-                    .map(|receipt| vec![receipt])
-                    .or_else(|error| {
-                        errors.insert(0, (format!("json from entire file"), error));
-                        serde_json::from_str::<Vec<Craft>>(all_lines.as_str())
-                    }) {
-                    Ok(old_receipts) => (old_receipts, Vec::new()),
-                    Err(error) => {
-                        errors.push((format!("jsonld from entire file"), error));
-                        (old_receipts, errors)
-                    }
+    pub fn receipts_path(&self) -> Path {
+        self.craft_home().join(RECEIPTS_FILE_NAME)
+    }
+    pub fn history_path(&self) -> Path {
+        self.craft_home().join(HISTORY_FILE_NAME)
+    }
+    pub fn write_history(&self) -> Result<Vec<String>> {
+        let mut history = self.history_path().read_lines().unwrap_or_default();
+        let ts = chrono::Utc::now().format("%Y/%m/%d %H:%M:%S %Z");
+        let args = iocore::env::args()
+            .into_iter()
+            .map(|arg| {
+                if arg.starts_with("-") {
+                    arg.to_string()
+                } else {
+                    format!("'{arg}'")
                 }
-            } else {
-                (old_receipts, errors)
-            }
-        } else {
-            return Err(Error::IOError(format!("{receipts_path} is not a file")));
-        })
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        let current = format!("[{ts}] {args}");
+        history.insert(0, current);
+        let data = history.join("\n");
+        self.history_path().write(data.as_bytes())?;
+        Ok(history)
     }
 }
-fn history_path() -> Path {
-    Path::new("~/.cargo/craft-history.txt").try_canonicalize()
-}
-pub fn write_history() -> Result<Vec<String>> {
-    let mut history = history_path().read_lines().unwrap_or_default();
-    let ts = chrono::Utc::now().format("%Y/%m/%d %H:%M:%S %Z");
-    let args = iocore::env::args()
-        .into_iter()
-        .map(|arg| {
-            if arg.starts_with("-") {
-                arg.to_string()
-            } else {
-                format!("'{arg}'")
-            }
-        })
-        .collect::<Vec<String>>()
-        .join(" ");
-    let current = format!("[{ts}] {args}");
-    history.insert(0, current);
-    let data = history.join("\n");
-    history_path().write(data.as_bytes())?;
-    Ok(history)
-}
 
 #[cfg(test)]
-mod test_craft {
-    use crate::{tera, Craft, Dependency, Result};
-    use chrono::{Local, TimeDelta};
-
+pub(crate) mod test_craft {
+    use crate::{
+        path_to_entry_path, render, tera, ClapExecuter, Craft, Dependency, Error, ErrorStyle,
+        MemorySink, Plan, PlanFormat, ReceiptStore, RecordingRunner, Result, Runner, Sink, Step,
+        VerifyStep,
+    };
     use clap::Parser;
     use iocore::{args_from_string, Path};
     use iocore_test::directory_path;
     use k9::assert_equal;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use toml::Value;
 
     fn craft_at_test_path(name: &str) -> Path {
         directory_path!()
@@ -691,48 +1019,37 @@ mod test_craft {
             .join("test")
             .join(name)
     }
+    /// `test_path` is the path of `name` among the files written by
+    /// the tests of `module`
+    pub(crate) fn test_path(module: &str, name: &str) -> Path {
+        craft_at_test_path(module).join(name)
+    }
     fn craft_from_args(args: &str) -> Craft {
-        Craft::parse_from(&args_from_string(args))
+        Craft::parse_from(args_from_string(args))
     }
     fn craft_from_name(name: &str) -> Craft {
         let at = craft_at_test_path(name);
-        Craft {
-            at: at,
-            package_name: None,
-            version: "0.1.0".to_string(),
-            dep: Vec::new(),
-            cli: true,
-            main: false,
-            bin: Vec::new(),
-            lib_path: None,
-            bin_path: ".".to_string(),
-            value_enum: false,
-            subcommands: false,
-            cli_barebones: false,
-            verbose: false,
-            quiet_add: true,
-            offline: true,
-            rollback: false,
-            default_bin_name: name.to_string(),
-            add_error_type: Vec::new(),
-            force: true,
-            silent: false,
-            started_at: Local::now(),
-            finished_at: Some(Local::now() + TimeDelta::new(3600, 0).unwrap()),
-            runtime_errors: Vec::new(),
-            description: None,
-            script: true,
-            subcommand_names: Vec::new(),
-        }
+        at.parent().unwrap().mkdir().unwrap();
+        Craft::builder(at)
+            .version("0.1.0")
+            .cli(true)
+            .default_bin_name(name)
+            .quiet_add(true)
+            .offline(true)
+            .force(true)
+            .script(true)
+            .build()
+            .unwrap()
     }
     #[test]
-    fn test_craft_context() {
+    fn test_craft_context() -> Result<()> {
         let craft = craft_from_args("craft test-crate-name");
-        assert_equal!(craft.crate_name(), "test-crate-name");
-        assert_equal!(craft.package_name(), "test_crate_name");
-        assert_equal!(craft.struct_name(), "TestCrateName");
+        assert_equal!(craft.crate_name()?, "test-crate-name");
+        assert_equal!(craft.package_name()?, "test_crate_name");
+        assert_equal!(craft.struct_name()?, "TestCrateName");
         assert_equal!(craft.version(), "0.0.1");
-        assert_equal!(craft.lib_path(), Path::raw("test-crate-name"));
+        assert_equal!(craft.lib_path()?, Path::raw("test-crate-name"));
+        Ok(())
     }
     #[test]
     fn test_craft_dependencies_with_features_string() -> Result<()> {
@@ -749,24 +1066,30 @@ mod test_craft {
             vec![
                 Dependency {
                     name: "reqwest".to_string(),
+                    version: None,
                     features: Some("blocking,deflate".to_string()),
                     dev: false,
                     build: false,
                     optional: false,
+                    ..Default::default()
                 },
                 Dependency {
                     name: "k9".to_string(),
+                    version: None,
                     features: None,
                     dev: true,
                     build: false,
                     optional: false,
+                    ..Default::default()
                 },
                 Dependency {
-                    name: "clap-builder".to_string(),
+                    name: "clap_builder".to_string(),
+                    version: None,
                     features: None,
                     dev: false,
                     build: true,
                     optional: false,
+                    ..Default::default()
                 }
             ]
         );
@@ -777,6 +1100,397 @@ mod test_craft {
         Ok(())
     }
     #[test]
+    fn test_craft_dependencies_with_versions() -> Result<()> {
+        let mut craft = craft_from_name("versioned-dependencies");
+        craft.dep = vec![
+            "serde_json@1.0.100".to_string(),
+            "tokio --version ^1.38 -Ffull".to_string(),
+        ];
+
+        let dependencies = craft.deps()?;
+        assert_equal!(
+            dependencies
+                .iter()
+                .map(|dep| (dep.name.clone(), dep.version.clone()))
+                .collect::<Vec<(String, Option<String>)>>(),
+            vec![
+                ("serde_json".to_string(), Some("1.0.100".to_string())),
+                ("tokio".to_string(), Some("^1.38".to_string())),
+            ]
+        );
+        assert_equal!(
+            craft
+                .cargo_add_steps()?
+                .iter()
+                .map(|step| step.to_string())
+                .filter(|step| step.contains('@'))
+                .collect::<Vec<String>>(),
+            vec![
+                format!("run `cargo add -q --offline 'serde_json@1.0.100'' in {}", craft.path()),
+                format!("run `cargo add -q --offline 'tokio@^1.38' -Ffull' in {}", craft.path()),
+            ]
+        );
+        assert_equal!(
+            dependencies[1].to_tera().get("version"),
+            Some(&Value::String("^1.38".to_string()))
+        );
+
+        craft.dep = vec!["tokio@>=1.38, <2 -F rt, macros".to_string()];
+        let dependencies = craft.deps()?;
+        assert_equal!(dependencies[0].version, Some(">=1.38,<2".to_string()));
+        assert_equal!(
+            dependencies[0].features(),
+            vec!["rt".to_string(), "macros".to_string()]
+        );
+
+        craft.dep = vec!["tokio@1 --version 1".to_string()];
+        assert_equal!(craft.deps().is_err(), true);
+        craft.dep = vec!["tokio@latest".to_string()];
+        assert_equal!(craft.deps().is_err(), true);
+        Ok(())
+    }
+    #[test]
+    fn test_craft_dependencies_from_path_and_git() -> Result<()> {
+        let mut craft = craft_from_name("sourced-dependencies");
+        let sibling = craft.path().parent().unwrap().join("sibling");
+        craft.dep = vec![
+            format!("sibling --path {sibling}"),
+            "upstream --git https://example.com/upstream.git --tag v1 --rename downstream"
+                .to_string(),
+        ];
+
+        let dependencies = craft.deps()?;
+        assert_equal!(dependencies[0].path, Some("../sibling".to_string()));
+        assert_equal!(dependencies[1].git, Some("https://example.com/upstream.git".to_string()));
+        assert_equal!(dependencies[1].tag, Some("v1".to_string()));
+        assert_equal!(dependencies[1].pascal_name(), "Downstream");
+        assert_equal!(
+            craft
+                .cargo_add_steps()?
+                .iter()
+                .map(|step| step.to_string())
+                .skip(3)
+                .collect::<Vec<String>>(),
+            vec![
+                format!("run `cargo add -q --offline sibling --path '../sibling'' in {}", craft.path()),
+                format!(
+                    "run `cargo add -q --offline upstream --git 'https://example.com/upstream.git' --tag 'v1' --rename downstream' in {}",
+                    craft.path()
+                ),
+            ]
+        );
+        let tera = dependencies[1].to_tera();
+        assert_equal!(tera.get("rename"), Some(&Value::String("downstream".to_string())));
+        assert_equal!(tera.get("package_name"), Some(&Value::String("downstream".to_string())));
+        assert_equal!(tera.get("tag"), Some(&Value::String("v1".to_string())));
+        assert_equal!(tera.get("branch"), None);
+
+        for spec in [
+            "sibling --path ../sibling --git https://example.com/sibling.git",
+            "upstream --branch main",
+            "upstream --git https://example.com/upstream.git --branch main --rev abc",
+            "upstream --rename downstream@1",
+        ] {
+            craft.dep = vec![spec.to_string()];
+            assert_equal!(craft.deps().is_err(), true);
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_craft_dependencies_for_targets() -> Result<()> {
+        let mut craft = craft_from_name("target-dependencies");
+        craft.dep = vec![
+            "heapless --no-default-features".to_string(),
+            r#"libc --target 'cfg(all(unix, not(target_os = "macos")))'"#.to_string(),
+            "windows-sys --target x86_64-pc-windows-msvc".to_string(),
+        ];
+
+        let dependencies = craft.deps()?;
+        assert_equal!(dependencies[0].no_default_features, true);
+        assert_equal!(
+            dependencies[1].target,
+            Some(r#"cfg(all(unix, not(target_os = "macos")))"#.to_string())
+        );
+        assert_equal!(
+            craft
+                .cargo_add_steps()?
+                .iter()
+                .map(|step| step.to_string())
+                .skip(3)
+                .collect::<Vec<String>>(),
+            vec![
+                format!(
+                    "run `cargo add -q --offline heapless --no-default-features' in {}",
+                    craft.path()
+                ),
+                format!(
+                    r#"run `cargo add -q --offline libc --target 'cfg(all(unix, not(target_os = "macos")))'' in {}"#,
+                    craft.path()
+                ),
+                format!(
+                    "run `cargo add -q --offline windows-sys --target 'x86_64-pc-windows-msvc'' in {}",
+                    craft.path()
+                ),
+            ]
+        );
+        assert_equal!(
+            dependencies[0].to_tera().get("default_features"),
+            Some(&Value::Boolean(false))
+        );
+        assert_equal!(
+            dependencies[1].to_tera().get("default_features"),
+            Some(&Value::Boolean(true))
+        );
+        assert_equal!(
+            dependencies[2].to_tera().get("target"),
+            Some(&Value::String("x86_64-pc-windows-msvc".to_string()))
+        );
+
+        for spec in [
+            "libc --target cfg(unix",
+            "libc --target 'cfg(target_os = linux)'",
+            "libc --target 'x86_64 linux'",
+            "libc --target 'cfg(unix)",
+        ] {
+            craft.dep = vec![spec.to_string()];
+            assert_equal!(craft.deps().is_err(), true);
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_craft_dependencies_from_local_git_repository() -> Result<()> {
+        let repository = craft_at_test_path("local-git-dependency");
+        repository.delete()?;
+        repository.join("src").mkdir()?;
+        repository
+            .join("Cargo.toml")
+            .write(b"[package]\nname = \"upstream\"\nversion = \"0.1.0\"\nedition = \"2021\"\n")?;
+        repository.join("src/lib.rs").write(b"")?;
+        let mut craft = craft_from_name("local-git-dependent");
+        craft.sink.remove(&craft.path())?;
+        craft.path().join("src").mkdir()?;
+        craft
+            .path()
+            .join("Cargo.toml")
+            .write(b"[package]\nname = \"dependent\"\nversion = \"0.1.0\"\nedition = \"2021\"\n[workspace]\n")?;
+        craft.path().join("src/lib.rs").write(b"")?;
+        for command in [
+            "git init -q -b main",
+            "git add .",
+            "git -c user.name=craft -c user.email=craft@localhost commit -q -m upstream",
+        ] {
+            assert_equal!(craft.shell_command(command, &repository)?, 0);
+        }
+        craft.dep = vec![format!(
+            "upstream --git file://{} --branch main --rename downstream",
+            repository.try_canonicalize()
+        )];
+
+        let steps = craft.cargo_add_steps()?;
+        assert_equal!(steps.last().unwrap().to_string().contains("--offline"), false);
+        steps.last().unwrap().execute(&craft)?;
+        let manifest = craft.manifest_path().read()?.parse::<toml::Table>()?;
+        let downstream = manifest["dependencies"]["downstream"].as_table().unwrap();
+        assert_equal!(downstream["package"].as_str(), Some("upstream"));
+        assert_equal!(downstream["branch"].as_str(), Some("main"));
+        repository.delete()?;
+        craft.path().delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_craft_error_conversions() -> Result<()> {
+        let mut craft = craft_from_name("error-conversions");
+        craft.dep = vec![
+            "serde_json --rename json".to_string(),
+            "toml".to_string(),
+            "regex".to_string(),
+            "k9 --dev".to_string(),
+            "reqwest --dev".to_string(),
+        ];
+        craft.add_error_type = vec![
+            "Pattern=regex::Error".to_string(),
+            "Utf8=std::string::FromUtf8Error".to_string(),
+            "Custom".to_string(),
+        ];
+
+        assert_equal!(
+            craft.error_conversions()?,
+            vec![
+                ("Json".to_string(), vec!["json::Error".to_string()]),
+                (
+                    "Toml".to_string(),
+                    vec!["toml::de::Error".to_string(), "toml::ser::Error".to_string()]
+                ),
+                ("Regex".to_string(), vec![]),
+                ("K9".to_string(), vec![]),
+                ("Reqwest".to_string(), vec![]),
+                ("Pattern".to_string(), vec!["regex::Error".to_string()]),
+                ("Utf8".to_string(), vec!["std::string::FromUtf8Error".to_string()]),
+                ("Custom".to_string(), vec![]),
+            ]
+        );
+        let errors = render(&craft, "errors.rs")?.unwrap_or_default();
+        for from in [
+            "impl From<json::Error> for Error",
+            "impl From<toml::ser::Error> for Error",
+            "impl From<regex::Error> for Error",
+            "impl From<std::string::FromUtf8Error> for Error",
+        ] {
+            assert_equal!(errors.contains(from), true);
+        }
+        assert_equal!(errors.matches("impl From<regex::Error>").count(), 1);
+        assert_equal!(errors.contains("Error::PatternError(e.to_string())"), true);
+        assert_equal!(errors.contains("reqwest::Error"), false);
+
+        craft.dep = vec!["Regex".to_string(), "regex".to_string()];
+        craft.add_error_type = vec!["RegexError".to_string()];
+        assert_equal!(craft.error_types()?, vec!["Regex".to_string()]);
+        Ok(())
+    }
+    #[test]
+    fn test_craft_error_conversions_of_feature_gated_error_types() -> Result<()> {
+        let mut craft = craft_from_name("feature-gated-error-conversions");
+        craft.dep = vec!["tokio".to_string()];
+        assert_equal!(
+            craft.error_conversions()?,
+            vec![("Tokio".to_string(), vec![])]
+        );
+        let errors = render(&craft, "errors.rs")?.unwrap_or_default();
+        assert_equal!(errors.contains("JoinError"), false);
+
+        for features in ["rt", "macros,full"] {
+            craft.dep = vec![format!("tokio -F {features}")];
+            assert_equal!(
+                craft.error_conversions()?,
+                vec![("Tokio".to_string(), vec!["tokio::task::JoinError".to_string()])]
+            );
+            let errors = render(&craft, "errors.rs")?.unwrap_or_default();
+            assert_equal!(errors.contains("impl From<tokio::task::JoinError> for Error"), true);
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_craft_error_exit_codes() -> Result<()> {
+        let mut craft = craft_from_name("error-exit-codes");
+        craft.dep = vec!["serde_json".to_string(), "reqwest".to_string(), "k9".to_string()];
+        craft.add_error_type = vec![
+            "Input=std::num::ParseIntError@usage".to_string(),
+            "Reqwest@tempfail".to_string(),
+            "Custom".to_string(),
+        ];
+
+        assert_equal!(
+            craft.error_exit_codes()?,
+            BTreeMap::from([
+                ("SerdeJson".to_string(), 65),
+                ("Reqwest".to_string(), 75),
+                ("K9".to_string(), 70),
+                ("Input".to_string(), 64),
+                ("Custom".to_string(), 70),
+            ])
+        );
+        for style in [ErrorStyle::Handrolled, ErrorStyle::Thiserror, ErrorStyle::Snafu] {
+            craft.error_style = style;
+            let errors = render(&craft, craft.errors_template_name()?)?.unwrap_or_default();
+            assert_equal!(errors.contains("=> 74,"), true);
+            assert_equal!(errors.contains("Error::InputError"), true);
+            assert_equal!(errors.contains("=> 64,"), true);
+            assert_equal!(errors.contains("ExitCode::from(error.exit_code())"), true);
+        }
+        craft.add_error_type = vec!["Custom@0".to_string()];
+        assert_equal!(craft.error_exit_codes().is_err(), true);
+        Ok(())
+    }
+    #[test]
+    fn test_craft_error_styles() -> Result<()> {
+        let mut craft = craft_from_name("error-styles");
+        craft.dep = vec!["regex".to_string()];
+        for (style, dependency, contents) in [
+            (ErrorStyle::Handrolled, None, "pub fn variant(&self) -> String"),
+            (
+                ErrorStyle::Thiserror,
+                Some("cargo add -q --offline thiserror"),
+                "#[error(\"RegexError: {0}\")]",
+            ),
+            (
+                ErrorStyle::Snafu,
+                Some("cargo add -q --offline snafu"),
+                "Error::RegexError { message: e.to_string() }",
+            ),
+            (
+                ErrorStyle::AnyhowApp,
+                Some("cargo add -q --offline anyhow"),
+                "pub type Result<T> = anyhow::Result<T>;",
+            ),
+        ] {
+            craft.error_style = style;
+            let errors = craft
+                .plan()?
+                .steps
+                .into_iter()
+                .find_map(|step| match step {
+                    Step::WriteFile { path, contents } if path.name() == "errors.rs" => Some(contents),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            assert_equal!(errors.contains(contents), true);
+            assert_equal!(errors.contains("pub enum Exit"), true);
+            let commands = craft.plan()?.commands();
+            for other in ["thiserror", "snafu", "anyhow"] {
+                let command = format!("cargo add -q --offline {other}");
+                assert_equal!(commands.contains(&command), dependency == Some(command.as_str()));
+            }
+        }
+        assert_equal!(
+            render(&craft, "dispatch.rs")?
+                .unwrap_or_default()
+                .contains("ParserDispatcher<E: std::fmt::Debug + std::fmt::Display>"),
+            true
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_craft_anyhow_app_errors() -> Result<()> {
+        let mut craft = craft_from_name("anyhow-app-errors");
+        craft.error_style = ErrorStyle::AnyhowApp;
+
+        let errors = render(&craft, craft.errors_template_name()?)?.unwrap_or_default();
+        assert_equal!(
+            errors.contains(
+                "#[allow(unused_imports)]\npub use anyhow::{anyhow, bail, ensure, Context, Error};"
+            ),
+            true
+        );
+        assert_equal!(errors.contains("pub type Result<T> = anyhow::Result<T>;"), true);
+        Ok(())
+    }
+    #[test]
+    fn test_craft_error_sources() -> Result<()> {
+        let mut craft = craft_from_name("error-sources");
+        craft.dep = vec!["serde_json".to_string()];
+        craft.error_sources = true;
+
+        let errors = render(&craft, craft.errors_template_name()?)?.unwrap_or_default();
+        for contents in [
+            "pub type Source = Arc<dyn std::error::Error + Send + Sync + 'static>;",
+            "SerdeJsonError {",
+            "fn source(&self) -> Option<&(dyn std::error::Error + 'static)>",
+            "impl From<serde_json::Error> for Error",
+            "location: Some($crate::Location {",
+        ] {
+            assert_equal!(errors.contains(contents), true);
+        }
+        assert_equal!(
+            render(&craft, "lib.rs")?
+                .unwrap_or_default()
+                .contains("pub use errors::{Error, Result, Exit, Location, Source};"),
+            true
+        );
+        craft.error_style = ErrorStyle::Thiserror;
+        assert_equal!(craft.errors_template_name().is_err(), true);
+        Ok(())
+    }
+    #[test]
     fn test_tera() -> Result<()> {
         let mut craft = craft_from_name("dependencies");
         craft.dep = vec![
@@ -788,17 +1502,422 @@ mod test_craft {
         Ok(())
     }
     #[test]
+    fn test_templates_override_embedded_templates() -> Result<()> {
+        let templates = craft_at_test_path("templates-override");
+        templates
+            .join("errors.rs.tera")
+            .write(b"pub enum Error { {% for name in craft_errors %}{{name}}Error,{% endfor %} }")?;
+        let mut craft = craft_from_name("templates");
+        craft.dep = vec!["regex".to_string()];
+        craft.templates = Some(templates.clone());
+
+        assert_equal!(
+            render(&craft, "errors.rs")?,
+            Some("pub enum Error { RegexError, }".to_string())
+        );
+        assert_equal!(render(&craft, "README.md")?, Some("# templates\n".to_string()));
+        templates.delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_broken_templates_return_template_errors() -> Result<()> {
+        let templates = craft_at_test_path("templates-broken");
+        let mut craft = craft_from_name("broken");
+        craft.templates = Some(templates.clone());
+        craft.runner = Runner::new(RecordingRunner::new());
+        craft.sink = Sink::new(MemorySink::new());
+
+        templates.join("lib.rs.tera").write(b"{{ undefined_variable }}")?;
+        assert_equal!(matches!(craft.go(), Err(Error::TemplateError(_))), true);
+
+        templates.join("lib.rs.tera").write(b"{% if unclosed %}")?;
+        assert_equal!(matches!(craft.go(), Err(Error::TemplateError(_))), true);
+        templates.delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_template_pack_replaces_builtin_templates() -> Result<()> {
+        let pack = craft_at_test_path("template-pack");
+        pack.join("craft-pack.toml").write(
+            [
+                "name = \"service\"",
+                "",
+                "[context]",
+                "team = \"platform\"",
+                "crate_name = \"ignored\"",
+                "",
+                "[[template]]",
+                "template = \"service.rs.tera\"",
+                "output = \"{{ lib_path }}/{{ package_name }}_service.rs\"",
+                "",
+                "[[template]]",
+                "template = \"README.md\"",
+                "output = \"README.md\"",
+                "when = [\"!craft_cli\"]",
+                "",
+                "[[template]]",
+                "template = \"main.rs.tera\"",
+                "output = \"{{ entry.path }}\"",
+                "each = \"crate_binaries\"",
+                "when = [\"craft_cli\"]",
+            ]
+            .join("\n")
+            .as_bytes(),
+        )?;
+        pack.join("service.rs.tera")
+            .write(b"// {{ crate_name }} owned by {{ team }}")?;
+        pack.join("main.rs.tera").write(b"fn main() {} // {{ entry.name }}")?;
+        let mut craft = craft_from_name("service-pack");
+        craft.pack = Some(pack.clone());
+        craft.bin = vec!["service-a".to_string(), "service-b".to_string()];
+
+        let ttargets = craft
+            .render_templates()?
+            .into_iter()
+            .map(|(rendered, entries)| {
+                (rendered, path_to_entry_path(entries[0].clone()).unwrap().to_string())
+            })
+            .collect::<Vec<(String, String)>>();
+        assert_equal!(
+            ttargets,
+            vec![
+                (
+                    "// service-pack owned by platform".to_string(),
+                    "service-pack/service_pack_service.rs".to_string()
+                ),
+                ("fn main() {} // service-a".to_string(), "./service-a.rs".to_string()),
+                ("fn main() {} // service-b".to_string(), "./service-b.rs".to_string()),
+                ("fn main() {} // service-pack".to_string(), "./service-pack.rs".to_string()),
+            ]
+        );
+
+        craft.workspace = true;
+        craft.bin = Vec::new();
+        let names = |craft: &Craft| -> Result<Vec<String>> {
+            Ok(craft
+                .render_templates()?
+                .into_iter()
+                .map(|(_, entries)| path_to_entry_path(entries[0].clone()).unwrap().name())
+                .collect())
+        };
+        assert_equal!(
+            names(&craft)?,
+            vec![".gitignore", ".rustfmt.toml", "rust-toolchain.toml", "README.md"]
+        );
+        assert!(names(&craft.member("alpha"))?.contains(&"alpha_service.rs".to_string()));
+        pack.delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_plan_does_not_touch_the_disk() -> Result<()> {
+        let mut craft = craft_from_name("planned");
+        craft.dep = vec!["regex".to_string()];
+        if craft.at.exists() {
+            craft.at.delete()?;
+        }
+        let plan = craft.plan()?;
+        assert_equal!(craft.at.exists(), false);
+        assert_equal!(
+            plan.written_paths()
+                .iter()
+                .map(|path| path.to_string().replace(&format!("{}/", craft.path()), ""))
+                .collect::<Vec<String>>(),
+            vec![
+                "Cargo.toml",
+                "planned/lib.rs",
+                "planned/dispatch.rs",
+                "planned/planned.rs",
+                "planned/errors.rs",
+                "planned.rs",
+                ".gitignore",
+                ".rustfmt.toml",
+                "rust-toolchain.toml",
+                "README.md",
+            ]
+        );
+        assert_equal!(
+            plan.commands()
+                .into_iter()
+                .filter(|command| !command.starts_with("rustfmt"))
+                .collect::<Vec<String>>(),
+            vec![
+                "cargo add -q --offline clap -F derive,env,string,unicode,wrap_help",
+                "cargo add -q --offline iocore",
+                "cargo add -q --offline serde -F derive",
+                "cargo add -q --offline regex",
+                "git init",
+                "git add .",
+                "cargo check --offline --quiet",
+                "cargo build --offline --quiet",
+                "cargo test --offline --quiet",
+                "cargo doc --offline --quiet",
+            ]
+        );
+        let json = plan.format(PlanFormat::Json)?;
+        assert_equal!(serde_json::from_str::<Plan>(&json)?, plan);
+        Ok(())
+    }
+    #[test]
+    fn test_verify_steps() -> Result<()> {
+        let mut craft = craft_from_name("verified");
+        let cargo_subcommands = |craft: &Craft| {
+            craft
+                .cargo_subcommand_steps()
+                .iter()
+                .map(|step| step.to_string())
+                .collect::<Vec<String>>()
+        };
+        craft.verify = vec![VerifyStep::None];
+        assert_equal!(cargo_subcommands(&craft), Vec::<String>::new());
+
+        craft.verify = vec![VerifyStep::Check, VerifyStep::Clippy, VerifyStep::Test];
+        assert_equal!(
+            cargo_subcommands(&craft),
+            vec![
+                format!("run `cargo check --offline --quiet' in {}", craft.path()),
+                format!("run `cargo clippy --offline --quiet' in {}", craft.path()),
+                format!("run `cargo test --offline --quiet' in {}", craft.path()),
+            ]
+        );
+        assert_equal!(
+            craft_from_args("craft --verify none crate").verify,
+            vec![VerifyStep::None]
+        );
+        assert_equal!(
+            craft_from_args("craft --verify check,clippy crate").verify,
+            vec![VerifyStep::Check, VerifyStep::Clippy]
+        );
+        assert_equal!(
+            craft_from_args("craft crate").verify,
+            vec![
+                VerifyStep::Check,
+                VerifyStep::Build,
+                VerifyStep::Test,
+                VerifyStep::Doc
+            ]
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_go_issues_commands_through_runner() -> Result<()> {
+        let runner = Arc::new(RecordingRunner::new());
+        let mut craft = craft_from_name("recorded");
+        craft.runner = Runner::shared(runner.clone());
+        craft.verify = vec![VerifyStep::Check];
+        craft.script = false;
+
+        craft.go()?;
+        assert_equal!(craft.manifest_path().is_file(), true);
+        assert_equal!(
+            runner.commands(),
+            vec![
+                "rustfmt tmp/test/recorded/recorded/lib.rs",
+                "rustfmt tmp/test/recorded/recorded/dispatch.rs",
+                "rustfmt tmp/test/recorded/recorded/recorded.rs",
+                "rustfmt tmp/test/recorded/recorded/errors.rs",
+                "rustfmt tmp/test/recorded/recorded.rs",
+                "cargo add -q --offline clap -F derive,env,string,unicode,wrap_help",
+                "cargo add -q --offline iocore",
+                "cargo add -q --offline serde -F derive",
+                "git init",
+                "git add .",
+                "cargo check --offline --quiet",
+            ]
+        );
+        craft.at.delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_go_fails_when_verification_fails() -> Result<()> {
+        let runner = Arc::new(RecordingRunner::new().fail("cargo test", 101));
+        let mut craft = craft_from_name("unverified");
+        craft.runner = Runner::shared(runner.clone());
+        craft.script = false;
+
+        assert_equal!(
+            craft.go(),
+            Err(Error::ShellCommandError(
+                "\"cargo test --offline --quiet\" failed with 101".to_string()
+            ))
+        );
+        assert_equal!(
+            runner.commands().last().cloned(),
+            Some("cargo test --offline --quiet".to_string())
+        );
+        craft.at.delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_go_writes_through_memory_sink() -> Result<()> {
+        let sink = Arc::new(MemorySink::new());
+        let mut craft = craft_from_name("in-memory");
+        craft.runner = Runner::new(RecordingRunner::new());
+        craft.sink = Sink::shared(sink.clone());
+        craft.workspace = true;
+        craft.members = vec!["core".to_string()];
+        craft.script = false;
+
+        craft.go()?;
+        assert_equal!(craft.at.exists(), false);
+        let files = sink.files();
+        assert_equal!(
+            files
+                .keys()
+                .map(|path| path.replace(&format!("{}/", craft.path().try_absolute()), ""))
+                .collect::<Vec<String>>(),
+            vec![
+                ".gitignore",
+                ".rustfmt.toml",
+                "Cargo.toml",
+                "README.md",
+                "core/Cargo.toml",
+                "core/README.md",
+                "core/core.rs",
+                "core/core/core.rs",
+                "core/core/dispatch.rs",
+                "core/core/errors.rs",
+                "core/core/lib.rs",
+                "rust-toolchain.toml",
+            ]
+        );
+        let manifest = &files[&craft.path_to("core/Cargo.toml").try_absolute().to_string()];
+        assert_equal!(manifest.contains("name = \"core\""), true);
+        assert_equal!(manifest.contains("edition.workspace = true"), true);
+        Ok(())
+    }
+    #[test]
+    fn test_run_records_history_and_receipt_in_craft_home() -> Result<()> {
+        let craft_home = craft_at_test_path("craft-home");
+        let mut craft = craft_from_name("craft-home-crate");
+        craft.runner = Runner::new(RecordingRunner::new());
+        craft.sink = Sink::new(MemorySink::new());
+        craft.script = false;
+        craft.craft_home = Some(craft_home.clone());
+        craft.no_history = true;
+        craft.no_receipt = true;
+
+        Craft::run(&craft)?;
+        assert_equal!(craft_home.exists(), false);
+
+        craft.no_history = false;
+        craft.no_receipt = false;
+        Craft::run(&craft)?;
+        assert_equal!(craft.history_path(), craft_home.join("craft-history.txt"));
+        assert_equal!(craft.history_path().read_lines()?.len(), 1);
+        let (receipts, errors) = ReceiptStore::new(craft.receipts_path()).read()?;
+        assert_equal!(errors.len(), 0);
+        assert_equal!(receipts.len(), 1);
+        assert_equal!(receipts[0].craft_home, Some(craft_home.clone()));
+        craft_home.delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_go_writes_dependencies_into_the_manifest() -> Result<()> {
+        let lockfile = craft_at_test_path("manifest-deps.lock");
+        lockfile.write(
+            b"[[package]]\nname = \"clap\"\nversion = \"4.5.20\"\n\n\
+              [[package]]\nname = \"iocore\"\nversion = \"3.0.3\"\n\n\
+              [[package]]\nname = \"serde\"\nversion = \"1.0.213\"\n\n\
+              [[package]]\nname = \"heapless\"\nversion = \"0.8.0\"\n",
+        )?;
+        let sink = Arc::new(MemorySink::new());
+        let runner = Arc::new(RecordingRunner::new());
+        let mut craft = craft_from_name("manifest-deps");
+        craft.runner = Runner::shared(runner.clone());
+        craft.sink = Sink::shared(sink.clone());
+        craft.manifest_deps = true;
+        craft.lockfile = Some(lockfile.clone());
+        craft.script = false;
+        craft.dep = vec![
+            "regex@1.10 -F unicode".to_string(),
+            "heapless --no-default-features".to_string(),
+            "libc@0.2 --target 'cfg(unix)'".to_string(),
+            "k9@0.12 --dev".to_string(),
+            "upstream --git https://example.com/upstream.git --rename downstream".to_string(),
+        ];
+
+        craft.go()?;
+        assert_equal!(
+            runner
+                .commands()
+                .iter()
+                .any(|command| command.starts_with("cargo add")),
+            false
+        );
+        let contents = sink.files()[&craft.manifest_path().try_absolute().to_string()].clone();
+        let manifest = contents.parse::<toml::Table>()?;
+        assert_equal!(
+            contents.contains(&[
+                "clap = { version = \"4.5.20\", features = [\"derive\", \"env\", \"string\", \"unicode\", \"wrap_help\"] }",
+                "iocore = \"3.0.3\"",
+                "serde = { version = \"1.0.213\", features = [\"derive\"] }",
+                "regex = { version = \"1.10\", features = [\"unicode\"] }",
+                "heapless = { version = \"0.8.0\", default-features = false }",
+                "downstream = { git = \"https://example.com/upstream.git\", package = \"upstream\" }",
+                "",
+            ]
+            .join("\n")),
+            true
+        );
+        assert_equal!(manifest["dev-dependencies"]["k9"].as_str(), Some("0.12"));
+        assert_equal!(
+            manifest["target"]["cfg(unix)"]["dependencies"]["libc"].as_str(),
+            Some("0.2")
+        );
+
+        craft.dep = vec!["no-such-crate".to_string()];
+        assert_equal!(craft.plan().is_err(), true);
+        craft.cargo_add_fallback = true;
+        assert_equal!(
+            craft.plan()?.commands().contains(&"cargo add -q --offline no-such-crate".to_string()),
+            true
+        );
+        lockfile.delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_craft_without_iocore_nor_serde() -> Result<()> {
+        let mut craft = craft_from_name("cargo-std-only");
+        craft.no_iocore = true;
+        craft.no_serde = true;
+
+        assert_equal!(
+            craft
+                .cargo_add_steps()?
+                .iter()
+                .map(|step| step.to_string())
+                .collect::<Vec<String>>(),
+            vec![format!(
+                "run `cargo add -q --offline clap -F derive,env,string,unicode,wrap_help' in {}",
+                craft.path()
+            )]
+        );
+        for template in ["errors.rs", "dispatch.rs"] {
+            let rendered = render(&craft, template)?.unwrap_or_default();
+            assert_equal!(rendered.contains("iocore"), false);
+            assert_equal!(rendered.contains("serde"), false);
+        }
+        craft.no_iocore = false;
+        assert_equal!(
+            render(&craft, "errors.rs")?
+                .unwrap_or_default()
+                .contains("impl From<iocore::Error> for Error"),
+            true
+        );
+        Ok(())
+    }
+    #[test]
     fn test_craft_paths() -> Result<()> {
         let mut craft = craft_from_name("dummy9");
         craft.main = false;
 
         craft.dep = Vec::new();
 
-        assert_equal!(craft.project_path().to_string(), "./tmp/test/dummy9");
-        assert_equal!(craft.lib_path().to_string(), "dummy9");
-        assert_equal!(craft.bin_path().to_string(), "tmp/test/dummy9");
+        assert_equal!(craft.project_path().relative_to_cwd().to_string(), "tmp/test/dummy9");
+        assert_equal!(craft.lib_path()?.to_string(), "dummy9");
+        assert_equal!(craft.bin_path()?.to_string(), ".");
         assert_equal!(craft.default_bin_name()?, "dummy9");
-        assert_equal!(craft.bin_names(), vec!["dummy9"]);
+        assert_equal!(craft.bin_names()?, vec!["dummy9"]);
         Ok(())
     }
 }
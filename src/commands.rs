@@ -0,0 +1,718 @@
+use crate::cli::{ClapExecuter, Craft, HISTORY_FILE_NAME, RECEIPTS_FILE_NAME};
+use crate::errors::{Error, Result};
+use crate::helpers::{absolute_path, default_craft_home, valid_date, valid_package_name};
+use crate::plan::PlanFormat;
+use crate::receipts::{decode_receipt, ReceiptStore};
+use crate::traceback;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use clap::{Args, Parser, ValueEnum};
+use iocore::Path;
+use serde::{Deserialize, Serialize};
+
+/// `CraftCommand` holds the commands of cargo-craft which work on
+/// previous runs rather than on a new crate. They are recognized
+/// by their name in place of `<AT>`, e.g.: `cargo craft receipts`
+/// (`cargo craft ./receipts` still generates a crate named
+/// `receipts').
+#[derive(Parser, Debug, Clone, PartialEq, Eq)]
+#[command(bin_name = "cargo craft")]
+pub enum CraftCommand {
+    /// lists the command-lines recorded in the history of cargo-craft
+    History(HistoryQuery),
+    /// lists the receipts of previous runs of cargo-craft
+    Receipts(ReceiptQuery),
+    /// generates a crate again from the receipt of a previous run
+    Replay(Replay),
+}
+
+impl CraftCommand {
+    pub const NAMES: [&'static str; 3] = ["history", "receipts", "replay"];
+
+    /// `detect` parses `args` into a [`CraftCommand`] when the
+    /// argument following the executable names one of [`CraftCommand::NAMES`]
+    pub fn detect(args: &[String]) -> Option<CraftCommand> {
+        let name = args.get(1)?;
+        if CraftCommand::NAMES.contains(&name.as_str()) {
+            Some(CraftCommand::parse_from(args))
+        } else {
+            None
+        }
+    }
+    pub fn run(&self) -> Result<()> {
+        let output = match self {
+            CraftCommand::History(query) => {
+                let path = query.filter.craft_home().join(HISTORY_FILE_NAME);
+                query.format(&query.entries(&path)?)?
+            }
+            CraftCommand::Receipts(query) => {
+                let path = query.filter.craft_home().join(RECEIPTS_FILE_NAME);
+                query.format(&query.entries(&path)?)?
+            }
+            CraftCommand::Replay(replay) => {
+                let path = replay.craft_home().join(RECEIPTS_FILE_NAME);
+                return replay.run(&path);
+            }
+        };
+        print!("{output}");
+        Ok(())
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ListFormat {
+    Table,
+    Json,
+}
+
+/// `QueryFilter` holds the options shared by `cargo craft history`
+/// and `cargo craft receipts`
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct QueryFilter {
+    #[arg(long, value_parser = valid_date, help = "lists entries from <SINCE> on, e.g.: `2025-01-31'")]
+    pub since: Option<NaiveDate>,
+
+    #[arg(long, value_parser = valid_date, help = "lists entries up to <UNTIL> inclusive")]
+    pub until: Option<NaiveDate>,
+
+    #[arg(long, help = "lists entries whose path contains <PATH>")]
+    pub path: Option<String>,
+
+    #[arg(long, help = "lists entries of the crate or package named <NAME>")]
+    pub name: Option<String>,
+
+    #[arg(short = 'n', long, help = "lists only the <LAST> most recent entries")]
+    pub last: Option<usize>,
+
+    #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+    pub format: ListFormat,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        value_parser = absolute_path,
+        help = "directory holding the history and the receipts of cargo-craft (defaults to `$CARGO_CRAFT_HOME', `$CARGO_HOME' or `~/.cargo')"
+    )]
+    pub craft_home: Option<Path>,
+}
+
+impl QueryFilter {
+    pub fn craft_home(&self) -> Path {
+        self.craft_home.clone().unwrap_or_else(default_craft_home)
+    }
+    fn admits(&self, date: NaiveDate, path: &str, names: &[String]) -> bool {
+        self.since.map(|since| date >= since).unwrap_or(true)
+            && self.until.map(|until| date <= until).unwrap_or(true)
+            && self
+                .path
+                .as_ref()
+                .map(|part| path.contains(part))
+                .unwrap_or(true)
+            && self
+                .name
+                .as_ref()
+                .map(|name| names.contains(name))
+                .unwrap_or(true)
+    }
+    fn last<T>(&self, mut entries: Vec<T>) -> Vec<T> {
+        if let Some(last) = self.last {
+            entries.drain(..entries.len().saturating_sub(last));
+        }
+        entries
+    }
+}
+
+/// `HistoryEntry` is a command-line recorded in the history,
+/// numbered from the oldest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub index: usize,
+    pub recorded_at: DateTime<Local>,
+    pub command: String,
+}
+
+impl HistoryEntry {
+    /// `parse` parses a line of the history as in
+    /// `[2025/01/31 12:00:00 UTC] 'cargo-craft' 'craft' '/tmp/crate' -c`
+    pub fn parse(index: usize, line: &str) -> Option<HistoryEntry> {
+        let (timestamp, command) = line.strip_prefix('[')?.split_once("] ")?;
+        let timestamp = timestamp.strip_suffix(" UTC").unwrap_or(timestamp);
+        let recorded_at = NaiveDateTime::parse_from_str(timestamp, "%Y/%m/%d %H:%M:%S")
+            .ok()?
+            .and_utc()
+            .with_timezone(&Local);
+        Some(HistoryEntry {
+            index,
+            recorded_at,
+            command: command.to_string(),
+        })
+    }
+    /// `names` are the arguments of the command-line along with the
+    /// directory names of those which are paths
+    fn names(&self) -> Vec<String> {
+        shlex::split(&self.command)
+            .unwrap_or_else(|| {
+                self.command
+                    .split_whitespace()
+                    .map(|arg| arg.trim_matches('\'').to_string())
+                    .collect()
+            })
+            .into_iter()
+            .flat_map(|arg| [Path::raw(&arg).name(), arg])
+            .collect()
+    }
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct HistoryQuery {
+    #[command(flatten)]
+    pub filter: QueryFilter,
+}
+
+impl HistoryQuery {
+    /// `entries` returns the entries of the history at `path`
+    /// matching the query, oldest first
+    pub fn entries(&self, path: &Path) -> Result<Vec<HistoryEntry>> {
+        let mut lines = if path.is_file() {
+            path.read_lines()?
+        } else {
+            Vec::new()
+        };
+        // the history is written most recent first
+        lines.reverse();
+        let entries = lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| HistoryEntry::parse(index + 1, line))
+            .filter(|entry| {
+                self.filter.admits(
+                    entry.recorded_at.date_naive(),
+                    &entry.command,
+                    &entry.names(),
+                )
+            })
+            .collect();
+        Ok(self.filter.last(entries))
+    }
+    pub fn format(&self, entries: &[HistoryEntry]) -> Result<String> {
+        Ok(match self.filter.format {
+            ListFormat::Json => format!("{}\n", serde_json::to_string_pretty(entries)?),
+            ListFormat::Table => {
+                let rows = entries
+                    .iter()
+                    .map(|entry| {
+                        vec![
+                            entry.index.to_string(),
+                            entry.recorded_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                            entry.command.clone(),
+                        ]
+                    })
+                    .collect::<Vec<Vec<String>>>();
+                table(&["INDEX", "RECORDED", "COMMAND"], &rows).join("")
+            }
+        })
+    }
+}
+
+/// `ReceiptEntry` is a receipt numbered from the oldest along with
+/// the duration of its run in milliseconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReceiptEntry {
+    pub index: usize,
+    pub duration_ms: Option<i64>,
+    #[serde(flatten)]
+    pub receipt: Craft,
+}
+
+impl ReceiptEntry {
+    pub fn new(index: usize, receipt: Craft) -> ReceiptEntry {
+        let duration_ms = receipt
+            .finished_at
+            .map(|finished_at| (finished_at - receipt.started_at).num_milliseconds());
+        ReceiptEntry {
+            index,
+            duration_ms,
+            receipt,
+        }
+    }
+    pub fn failed(&self) -> bool {
+        !self.receipt.runtime_errors.is_empty()
+    }
+    fn names(&self) -> Vec<String> {
+        [self.receipt.crate_name(), self.receipt.package_name()]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptQuery {
+    #[command(flatten)]
+    pub filter: QueryFilter,
+
+    #[arg(
+        long,
+        conflicts_with = "succeeded",
+        help = "lists only the runs which failed"
+    )]
+    pub failed: bool,
+
+    #[arg(long, help = "lists only the runs which succeeded")]
+    pub succeeded: bool,
+}
+
+impl ReceiptQuery {
+    /// `entries` returns the receipts at `path` matching the query,
+    /// oldest first
+    pub fn entries(&self, path: &Path) -> Result<Vec<ReceiptEntry>> {
+        let receipts = ReceiptStore::new(path.clone()).read()?.0;
+        let entries = receipts
+            .into_iter()
+            .enumerate()
+            .map(|(index, receipt)| ReceiptEntry::new(index + 1, receipt))
+            .filter(|entry| {
+                (!self.failed || entry.failed()) && (!self.succeeded || !entry.failed())
+            })
+            .filter(|entry| {
+                self.filter.admits(
+                    entry.receipt.started_at.date_naive(),
+                    &entry.receipt.path().to_string(),
+                    &entry.names(),
+                )
+            })
+            .collect();
+        Ok(self.filter.last(entries))
+    }
+    pub fn format(&self, entries: &[ReceiptEntry]) -> Result<String> {
+        Ok(match self.filter.format {
+            ListFormat::Json => format!("{}\n", serde_json::to_string_pretty(entries)?),
+            ListFormat::Table => {
+                let rows = entries
+                    .iter()
+                    .map(|entry| {
+                        vec![
+                            entry.index.to_string(),
+                            entry
+                                .receipt
+                                .started_at
+                                .format("%Y-%m-%d %H:%M:%S")
+                                .to_string(),
+                            entry
+                                .duration_ms
+                                .map(|ms| format!("{:.2}s", ms as f64 / 1000.0))
+                                .unwrap_or_else(|| "-".to_string()),
+                            if entry.failed() { "failed" } else { "ok" }.to_string(),
+                            entry.receipt.package_name().unwrap_or_default(),
+                            entry.receipt.path().to_string(),
+                        ]
+                    })
+                    .collect::<Vec<Vec<String>>>();
+                let lines = table(
+                    &["INDEX", "STARTED", "DURATION", "STATUS", "PACKAGE", "PATH"],
+                    &rows,
+                );
+                let mut output = lines[0].clone();
+                for (line, entry) in lines[1..].iter().zip(entries) {
+                    output.push_str(line);
+                    for error in entry.receipt.runtime_errors.iter() {
+                        for line in error.to_string().trim().lines() {
+                            output.push_str(&format!("    {line}\n"));
+                        }
+                    }
+                }
+                output
+            }
+        })
+    }
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    #[arg(
+        help = "index of the receipt as listed by `cargo craft receipts' or path to a file holding a receipt in JSON"
+    )]
+    pub receipt: String,
+
+    #[arg(
+        long,
+        value_parser = absolute_path,
+        help = "generates the crate at <AT> instead of the path of the receipt"
+    )]
+    pub at: Option<Path>,
+
+    #[arg(long, value_parser = valid_package_name)]
+    pub package_name: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "human",
+        help = "prints the files that would be written and the commands that would run (`human' or `json') without touching the disk"
+    )]
+    pub dry_run: Option<PlanFormat>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        value_parser = absolute_path,
+        help = "directory holding the history and the receipts of cargo-craft (defaults to `$CARGO_CRAFT_HOME', `$CARGO_HOME' or `~/.cargo')"
+    )]
+    pub craft_home: Option<Path>,
+}
+
+impl Replay {
+    pub fn craft_home(&self) -> Path {
+        self.craft_home.clone().unwrap_or_else(default_craft_home)
+    }
+    /// `craft` returns the [`Craft`] of the receipt, out of the
+    /// receipts at `receipts_path` when given by index, with the
+    /// bookkeeping of its previous run cleared
+    pub fn craft(&self, receipts_path: &Path) -> Result<Craft> {
+        let mut craft = match self.receipt.parse::<usize>() {
+            Ok(index) => {
+                let receipts = ReceiptStore::new(receipts_path.clone()).read()?.0;
+                index
+                    .checked_sub(1)
+                    .and_then(|index| receipts.get(index).cloned())
+                    .ok_or_else(|| {
+                        traceback!(
+                            ParseError,
+                            "no receipt at index {} among the {} of {}",
+                            index,
+                            receipts.len(),
+                            receipts_path
+                        )
+                    })?
+            }
+            Err(_) => {
+                let path = Path::new(&self.receipt);
+                serde_json::from_str::<serde_json::Value>(&path.read()?)
+                    .and_then(decode_receipt)
+                    .map_err(|error| Error::JsonError(format!("{path}: {error}")))?
+            }
+        };
+        craft.started_at = Local::now();
+        craft.finished_at = None;
+        craft.runtime_errors.clear();
+        if let Some(at) = &self.at {
+            craft.at = at.clone();
+        }
+        if let Some(package_name) = &self.package_name {
+            craft.package_name = Some(package_name.clone());
+        }
+        if self.dry_run.is_some() {
+            craft.dry_run = self.dry_run;
+        }
+        if self.craft_home.is_some() {
+            craft.craft_home = self.craft_home.clone();
+        }
+        Ok(craft)
+    }
+    /// `run` generates the crate of the receipt the same way
+    /// `cargo craft` does, recording a new receipt of its own
+    pub fn run(&self, receipts_path: &Path) -> Result<()> {
+        let craft = self.craft(receipts_path)?;
+        Craft::run(&craft).inspect_err(|error| {
+            let mut receipt = craft.clone();
+            receipt.runtime_errors.push(error.clone());
+            receipt.write_receipt().unwrap_or_default();
+        })
+    }
+}
+
+/// `table` aligns `rows` under `header` returning one line per row
+/// after that of the header
+fn table(header: &[&str], rows: &[Vec<String>]) -> Vec<String> {
+    let mut widths = header.iter().map(|name| name.len()).collect::<Vec<usize>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let last = cells.len() - 1;
+        let mut line = cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                if index == last {
+                    cell.to_string()
+                } else {
+                    format!("{cell:<width$}", width = widths[index])
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("  ");
+        line.push('\n');
+        line
+    };
+    let mut lines = vec![line(header.to_vec())];
+    lines.extend(
+        rows.iter()
+            .map(|row| line(row.iter().map(|cell| cell.as_str()).collect())),
+    );
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::test_craft::test_path;
+    use crate::{CraftBuilder, Error};
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_detect_commands() {
+        let args = |args: &[&str]| {
+            args.iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            CraftCommand::detect(&args(&["cargo-craft", "/tmp/history"])),
+            None
+        );
+        assert_eq!(
+            CraftCommand::detect(&args(&["cargo-craft", "./receipts"])),
+            None
+        );
+        let Some(CraftCommand::Receipts(query)) = CraftCommand::detect(&args(&[
+            "cargo-craft",
+            "receipts",
+            "--failed",
+            "--since",
+            "2025-01-31",
+            "--format",
+            "json",
+        ])) else {
+            panic!("expected `receipts'");
+        };
+        assert!(query.failed);
+        assert_eq!(query.filter.since, NaiveDate::from_ymd_opt(2025, 1, 31));
+        assert_eq!(query.filter.format, ListFormat::Json);
+        assert!(CraftCommand::try_parse_from([
+            "cargo-craft",
+            "receipts",
+            "--failed",
+            "--succeeded"
+        ])
+        .is_err());
+    }
+    #[test]
+    fn test_query_history() -> Result<()> {
+        let path = test_path("commands", "history/craft-history.txt");
+        path.write(
+            b"[2025/03/02 10:00:00 UTC] 'cargo-craft' 'craft' '/tmp/beta' -c\n\
+              [2025/02/01 10:00:00 UTC] 'cargo-craft' 'craft' '/srv/alpha' --package-name 'al'\n\
+              not a history entry\n\
+              [2025/01/01 10:00:00 UTC] 'cargo-craft' 'craft' '/tmp/alpha'",
+        )?;
+        let query = |args: &[&str]| {
+            let mut command = vec!["cargo-craft", "history"];
+            command.extend(args);
+            match CraftCommand::try_parse_from(command).unwrap() {
+                CraftCommand::History(query) => query,
+                command => panic!("unexpected {command:#?}"),
+            }
+        };
+        let indexes = |entries: Vec<HistoryEntry>| {
+            entries
+                .iter()
+                .map(|entry| entry.index)
+                .collect::<Vec<usize>>()
+        };
+
+        assert_eq!(indexes(query(&[]).entries(&path)?), vec![1, 3, 4]);
+        assert_eq!(
+            indexes(query(&["--name", "alpha"]).entries(&path)?),
+            vec![1, 3]
+        );
+        assert_eq!(indexes(query(&["--name", "al"]).entries(&path)?), vec![3]);
+        assert_eq!(
+            indexes(query(&["--path", "/tmp/"]).entries(&path)?),
+            vec![1, 4]
+        );
+        assert_eq!(
+            indexes(query(&["--since", "2025-02-01"]).entries(&path)?),
+            vec![3, 4]
+        );
+        assert_eq!(
+            indexes(query(&["--until", "2025-02-01", "-n", "1"]).entries(&path)?),
+            vec![3]
+        );
+        let table = query(&[]).format(&query(&["-n", "3"]).entries(&path)?[..1])?;
+        assert_eq!(table.lines().count(), 2);
+        assert!(table.lines().next().unwrap().starts_with("INDEX  RECORDED"));
+        assert!(table.ends_with("'cargo-craft' 'craft' '/tmp/alpha'\n"));
+        path.parent().unwrap().delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_query_history_of_paths_with_spaces() -> Result<()> {
+        let path = test_path("commands", "history-spaces/craft-history.txt");
+        path.write(
+            b"[2025/02/01 10:00:00 UTC] 'cargo-craft' 'craft' '/tmp/my crate' -c\n\
+              [2025/01/01 10:00:00 UTC] 'cargo-craft' 'craft' '/tmp/my'",
+        )?;
+        let query = |name: &str| match CraftCommand::try_parse_from([
+            "cargo-craft",
+            "history",
+            "--name",
+            name,
+        ])
+        .unwrap()
+        {
+            CraftCommand::History(query) => query,
+            command => panic!("unexpected {command:#?}"),
+        };
+        let indexes = |entries: Vec<HistoryEntry>| {
+            entries
+                .iter()
+                .map(|entry| entry.index)
+                .collect::<Vec<usize>>()
+        };
+
+        assert_eq!(indexes(query("my crate").entries(&path)?), vec![2]);
+        assert_eq!(indexes(query("my").entries(&path)?), vec![1]);
+        assert_eq!(indexes(query("crate").entries(&path)?), Vec::<usize>::new());
+        path.parent().unwrap().delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_query_receipts() -> Result<()> {
+        let path = test_path("commands", "receipts/craft-receipts.ldjson");
+        let started_at = Local::now() - TimeDelta::days(10);
+        let receipt = |at: &str, days: i64, failure: Option<&str>| {
+            let mut receipt = CraftBuilder::new(at).build().unwrap();
+            receipt.started_at = started_at + TimeDelta::days(days);
+            receipt.finished_at = Some(receipt.started_at + TimeDelta::milliseconds(1500));
+            receipt.runtime_errors = failure
+                .map(|failure| vec![Error::ShellCommandError(failure.to_string())])
+                .unwrap_or_default();
+            serde_json::to_string(&receipt).unwrap()
+        };
+        path.write(
+            [
+                receipt("/tmp/alpha", 0, None),
+                receipt("/srv/beta", 5, Some("\"cargo add regex\" failed with 101")),
+                receipt("/tmp/gamma", 9, None),
+            ]
+            .join("\n")
+            .as_bytes(),
+        )?;
+        let query = |args: &[&str]| {
+            let mut command = vec!["cargo-craft", "receipts"];
+            command.extend(args);
+            match CraftCommand::try_parse_from(command).unwrap() {
+                CraftCommand::Receipts(query) => query,
+                command => panic!("unexpected {command:#?}"),
+            }
+        };
+        let indexes = |entries: Vec<ReceiptEntry>| {
+            entries
+                .iter()
+                .map(|entry| entry.index)
+                .collect::<Vec<usize>>()
+        };
+        let since = (started_at + TimeDelta::days(5)).date_naive().to_string();
+
+        assert_eq!(indexes(query(&[]).entries(&path)?), vec![1, 2, 3]);
+        assert_eq!(indexes(query(&["--failed"]).entries(&path)?), vec![2]);
+        assert_eq!(
+            indexes(query(&["--succeeded", "-n", "1"]).entries(&path)?),
+            vec![3]
+        );
+        assert_eq!(
+            indexes(query(&["--since", &since]).entries(&path)?),
+            vec![2, 3]
+        );
+        assert_eq!(
+            indexes(query(&["--name", "gamma"]).entries(&path)?),
+            vec![3]
+        );
+        assert_eq!(
+            indexes(query(&["--path", "/tmp/"]).entries(&path)?),
+            vec![1, 3]
+        );
+        assert_eq!(
+            indexes(query(&[]).entries(&test_path("commands", "receipts/missing.ldjson"))?),
+            Vec::<usize>::new()
+        );
+
+        let failed = query(&["--failed"]);
+        let table = failed.format(&failed.entries(&path)?)?;
+        let row = table
+            .lines()
+            .nth(1)
+            .unwrap()
+            .split_whitespace()
+            .collect::<Vec<&str>>();
+        assert_eq!(row[0], "2");
+        assert_eq!(row[3..], ["1.50s", "failed", "beta", "/srv/beta"]);
+        assert!(table.contains("    ShellCommandError: \"cargo add regex\" failed with 101\n"));
+        let json = query(&["--format", "json"]).format(&failed.entries(&path)?)?;
+        let json = serde_json::from_str::<serde_json::Value>(&json)?;
+        assert_eq!(json[0]["index"], 2);
+        assert_eq!(json[0]["duration_ms"], 1500);
+        assert_eq!(json[0]["at"], "/srv/beta");
+        assert!(json[0]["runtime_errors"][0]["ShellCommandError"].is_string());
+        path.parent().unwrap().delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_replay_receipt() -> Result<()> {
+        let path = test_path("commands", "replay/craft-receipts.ldjson");
+        let mut receipt = CraftBuilder::new("/tmp/alpha")
+            .cli(true)
+            .subcommands(["init"])
+            .dependency("regex")
+            .build()?;
+        receipt.started_at = Local::now() - TimeDelta::days(30);
+        receipt.finished_at = Some(receipt.started_at + TimeDelta::seconds(30));
+        receipt.runtime_errors = vec![Error::RuntimeError("timed out".to_string())];
+        path.write(format!("{}\n", serde_json::to_string(&receipt)?).as_bytes())?;
+        let single = test_path("commands", "replay/receipt.json");
+        single.write(serde_json::to_string_pretty(&receipt)?.as_bytes())?;
+        let replay = |args: &[&str]| {
+            let mut command = vec!["cargo-craft", "replay"];
+            command.extend(args);
+            match CraftCommand::try_parse_from(command).unwrap() {
+                CraftCommand::Replay(replay) => replay,
+                command => panic!("unexpected {command:#?}"),
+            }
+        };
+
+        let craft = replay(&["1"]).craft(&path)?;
+        assert_eq!(craft.at, receipt.at);
+        assert_eq!(craft.subcommand_names, vec!["init"]);
+        assert_eq!(craft.dep, vec!["regex"]);
+        assert!(craft.started_at > receipt.started_at);
+        assert_eq!(craft.finished_at, None);
+        assert_eq!(craft.runtime_errors, Vec::new());
+
+        let craft = replay(&[
+            &single.to_string(),
+            "--at",
+            "/tmp/beta",
+            "--package-name",
+            "gamma",
+            "--dry-run=json",
+        ])
+        .craft(&path)?;
+        assert_eq!(craft.at, Path::new("/tmp/beta"));
+        assert_eq!(craft.package_name()?, "gamma");
+        assert_eq!(craft.dry_run, Some(PlanFormat::Json));
+        assert_eq!(craft.subcommand_names, vec!["init"]);
+
+        for missing in ["0", "2"] {
+            assert!(replay(&[missing]).craft(&path).is_err());
+        }
+        assert!(replay(&["1"])
+            .craft(&test_path("commands", "replay/missing.ldjson"))
+            .is_err());
+        path.parent().unwrap().delete()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+use crate::cli::Craft;
+use crate::errors::{Error, Result};
+use crate::helpers::default_craft_home;
+use crate::interactive::Interview;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, CommandFactory, FromArgMatches};
+use iocore::Path;
+use toml::{Table, Value};
+
+pub const CONFIG_FILE_NAME: &str = "craft.toml";
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".craft.toml";
+
+/// `CraftConfig` holds the defaults of [`Craft`] read from the
+/// configuration of the user (`craft.toml` in [`default_craft_home`])
+/// then from that of the project (the nearest `.craft.toml` from the
+/// current directory up). Options are named after their long flag or
+/// after the field of [`Craft`] and named presets are selected with
+/// `--preset <NAME>`:
+///
+/// ```toml
+/// dep = ["serde_json"]
+/// quiet-add = true
+/// offline = true
+///
+/// [preset.service]
+/// cli = true
+/// subcommands = true
+/// verify = ["clippy", "test"]
+/// ```
+///
+/// Options given on the command-line or through the environment take
+/// precedence over presets, which take precedence over defaults. Lists
+/// given on the command-line replace those of the configuration.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CraftConfig {
+    sources: Vec<(Path, Table)>,
+}
+
+impl CraftConfig {
+    /// `paths` are the configuration files of the user and of the
+    /// project at the current directory, whether they exist or not
+    pub fn paths() -> Vec<Path> {
+        CraftConfig::paths_from(&Path::cwd())
+    }
+    /// `paths_from` are the configuration files of the user and of the
+    /// project at `directory`, the closest `.craft.toml` among its
+    /// ancestors
+    pub fn paths_from(directory: &Path) -> Vec<Path> {
+        let mut paths = vec![default_craft_home().join(CONFIG_FILE_NAME)];
+        let directory = directory.try_canonicalize().to_path_buf();
+        // walks the ancestors of `std::path::Path`, for the parent of
+        // an `iocore::Path` is never `None`
+        if let Some(path) = directory
+            .ancestors()
+            .map(|ancestor| Path::from(ancestor).join(PROJECT_CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+        {
+            paths.push(path);
+        }
+        paths
+    }
+    /// `load` reads the files among `paths` which exist, the latter
+    /// taking precedence over the former
+    pub fn load(paths: &[Path]) -> Result<CraftConfig> {
+        let mut sources = Vec::<(Path, Table)>::new();
+        for path in paths.iter().filter(|path| path.is_file()) {
+            let table = path
+                .read()?
+                .parse::<Table>()
+                .map_err(|error| Error::DeserializationError(format!("{path}: {error}")))?;
+            sources.push((path.clone(), table));
+        }
+        Ok(CraftConfig { sources })
+    }
+    pub fn sources(&self) -> Vec<Path> {
+        self.sources.iter().map(|(path, _)| path.clone()).collect()
+    }
+    /// `options` merges the defaults and the preset named `preset` of
+    /// every source, along with the source of each option
+    pub fn options(&self, preset: Option<&str>) -> Result<Vec<(String, Value, Path)>> {
+        let mut options = Vec::<(String, Value, Path)>::new();
+        let mut merge = |path: &Path, table: &Table| {
+            for (key, value) in table.iter() {
+                options.retain(|(other, _, _)| other != key);
+                options.push((key.clone(), value.clone(), path.clone()));
+            }
+        };
+        for (path, table) in self.sources.iter() {
+            let mut defaults = table.clone();
+            defaults.remove("preset");
+            merge(path, &defaults);
+        }
+        if let Some(preset) = preset {
+            let mut found = false;
+            for (path, table) in self.sources.iter() {
+                match table.get("preset").and_then(|presets| presets.get(preset)) {
+                    Some(Value::Table(options)) => {
+                        found = true;
+                        merge(path, options);
+                    }
+                    Some(_) => {
+                        return Err(Error::DeserializationError(format!(
+                            "{path}: preset {preset:#?} is not a table"
+                        )))
+                    }
+                    None => {}
+                }
+            }
+            if !found {
+                return Err(Error::ParseError(format!(
+                    "no preset named {:#?} in {}",
+                    preset,
+                    match self.sources.len() {
+                        0 => "the absence of configuration files".to_string(),
+                        _ => self
+                            .sources()
+                            .iter()
+                            .map(|path| path.to_string())
+                            .collect::<Vec<String>>()
+                            .join(", "),
+                    }
+                )));
+            }
+        }
+        Ok(options)
+    }
+    /// `parse` parses `args` as the command-line of [`Craft`], filling
+    /// the options it leaves out from the configuration
+    pub fn parse(&self, args: &[String]) -> std::result::Result<Craft, clap::Error> {
+        let mut command = Craft::command();
+        let matches = command
+            .clone()
+            .ignore_errors(true)
+            .try_get_matches_from(args)?;
+        let preset = matches.get_one::<String>("preset").cloned();
+        let options = self
+            .options(preset.as_deref())
+            .map_err(|error| command.error(ErrorKind::InvalidValue, error))?;
+        let arguments = command.get_arguments().cloned().collect::<Vec<Arg>>();
+        // options given on the command-line or through the environment take precedence
+        let given = |arg: &Arg| {
+            matches!(
+                matches.value_source(arg.get_id().as_str()),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+        let mut args = args.to_vec();
+        for (key, value, path) in options {
+            let arg = arguments
+                .iter()
+                .find(|arg| {
+                    arg.get_long().is_some()
+                        && (arg.get_id() == key.replace('-', "_").as_str()
+                            || arg.get_long() == Some(key.as_str()))
+                })
+                .ok_or_else(|| {
+                    command.error(
+                        ErrorKind::UnknownArgument,
+                        format!("{path}: unknown option {key:#?}"),
+                    )
+                })?;
+            // so are the options conflicting with one given on the command-line
+            if given(arg)
+                || arguments.iter().filter(|other| given(other)).any(|other| {
+                    conflicting(&command, arg, other) || conflicting(&command, other, arg)
+                })
+            {
+                continue;
+            }
+            args.extend(option_args(arg, &value).map_err(|error| {
+                command.error(ErrorKind::InvalidValue, format!("{path}: {error}"))
+            })?);
+        }
+        let matches = command.try_get_matches_from_mut(&args)?;
+        Craft::from_arg_matches(&matches)
+    }
+}
+
+/// `conflicting` tells whether `arg` declares a conflict with `other`
+fn conflicting(command: &clap::Command, arg: &Arg, other: &Arg) -> bool {
+    command
+        .get_arg_conflicts_with(arg)
+        .iter()
+        .any(|conflict| conflict.get_id() == other.get_id())
+}
+
+/// `option_args` turns an option of the configuration into arguments
+/// of the command-line
+fn option_args(arg: &Arg, value: &Value) -> std::result::Result<Vec<String>, String> {
+    let long = arg.get_long().unwrap_or_default();
+    if matches!(arg.get_action(), ArgAction::SetTrue | ArgAction::SetFalse) {
+        return match value {
+            Value::Boolean(true) => Ok(vec![format!("--{long}")]),
+            Value::Boolean(false) => Ok(Vec::new()),
+            value => Err(format!("{long:#?} takes a boolean rather than {value}")),
+        };
+    }
+    let values = match value {
+        Value::Array(values) => values.clone(),
+        value => vec![value.clone()],
+    };
+    values
+        .into_iter()
+        .map(|value| match value {
+            Value::String(value) => Ok(format!("--{long}={value}")),
+            Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => {
+                Ok(format!("--{long}={value}"))
+            }
+            value => Err(format!("{long:#?} takes strings rather than {value}")),
+        })
+        .collect()
+}
+
+impl Craft {
+    /// `parse_with_config` parses `args` along with the configuration
+    /// files of [`CraftConfig::paths`] unless `--no-config` is given,
+    /// interviewing the user first when `--interactive` is given
+    pub fn parse_with_config(args: &[String]) -> std::result::Result<Craft, clap::Error> {
+        let mut command = Craft::command();
+        let matches = command
+            .clone()
+            .ignore_errors(true)
+            .try_get_matches_from(args)?;
+        let config = if matches.get_flag("no_config") {
+            CraftConfig::default()
+        } else {
+            CraftConfig::load(&CraftConfig::paths())
+                .map_err(|error| command.error(ErrorKind::Io, error))?
+        };
+        if matches.get_flag("interactive") {
+            return Interview::new(std::io::stdin().lock(), std::io::stderr()).craft(&config, args);
+        }
+        config.parse(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::test_craft::test_path;
+    use crate::VerifyStep;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        let mut command = vec!["cargo-craft".to_string(), "/tmp/configured".to_string()];
+        command.extend(args.iter().map(|arg| arg.to_string()));
+        command
+    }
+    #[test]
+    fn test_parse_with_defaults_and_presets() -> Result<()> {
+        let user = test_path("config", "parse/craft.toml");
+        user.write(
+            b"dep = [\"serde_json\"]\n\
+              quiet-add = true\n\
+              offline = true\n\
+              verify = \"check\"\n\
+              [preset.service]\n\
+              cli = true\n\
+              subcommands = true\n\
+              subcommand = [\"serve\"]\n\
+              verify = [\"clippy\", \"test\"]\n",
+        )?;
+        let project = test_path("config", "parse/project/.craft.toml");
+        project.write(b"offline = false\n[preset.service]\ndescription = \"a service\"\n")?;
+        let config = CraftConfig::load(&[
+            user.clone(),
+            project.clone(),
+            test_path("config", "parse/missing"),
+        ])?;
+        assert_eq!(config.sources(), vec![user.clone(), project.clone()]);
+        let parse = |args: &[String]| config.parse(args).map_err(|error| error.to_string());
+
+        let craft = parse(&args(&[])).unwrap();
+        assert_eq!(craft.dep, vec!["serde_json"]);
+        assert!(craft.quiet_add);
+        assert!(!craft.offline);
+        assert!(!craft.cli);
+        assert_eq!(craft.verify, vec![VerifyStep::Check]);
+
+        let craft = parse(&args(&[
+            "--preset", "service", "-d", "regex", "--verify", "doc",
+        ]))
+        .unwrap();
+        assert_eq!(craft.preset, Some("service".to_string()));
+        assert_eq!(craft.dep, vec!["regex"]);
+        assert!(craft.cli && craft.subcommands);
+        assert_eq!(craft.subcommand_names, vec!["serve"]);
+        assert_eq!(craft.verify, vec![VerifyStep::Doc]);
+        assert_eq!(craft.description, Some("a service".to_string()));
+
+        // options of presets satisfy the requirements of the command-line
+        let craft = parse(&args(&["--preset=service", "-C", "stop"])).unwrap();
+        assert_eq!(craft.subcommand_names, vec!["stop"]);
+        assert!(parse(&args(&["-C", "stop"])).is_err());
+
+        let error = parse(&args(&["--preset", "worker"])).unwrap_err();
+        assert!(error.contains("no preset named \"worker\""), "{error}");
+
+        project.write(b"verify = [\"lint\"]\n")?;
+        let error = CraftConfig::load(std::slice::from_ref(&project))?
+            .parse(&args(&[]))
+            .unwrap_err();
+        assert!(error.to_string().contains("lint"), "{error}");
+        project.write(b"colour = true\n")?;
+        let error = CraftConfig::load(std::slice::from_ref(&project))?
+            .parse(&args(&[]))
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("unknown option \"colour\""),
+            "{error}"
+        );
+        project.write(b"offline = \"yes\"\n")?;
+        let error = CraftConfig::load(std::slice::from_ref(&project))?
+            .parse(&args(&[]))
+            .unwrap_err();
+        assert!(error.to_string().contains("takes a boolean"), "{error}");
+        project.write(b"offline = \n")?;
+        assert!(CraftConfig::load(std::slice::from_ref(&project)).is_err());
+
+        user.parent().unwrap().delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_parse_leaves_out_options_conflicting_with_the_command_line() -> Result<()> {
+        let project = test_path("config", "conflicts/.craft.toml");
+        project.write(b"main = true\n")?;
+        let config = CraftConfig::load(std::slice::from_ref(&project))?;
+        assert!(config.parse(&args(&[])).unwrap().main);
+        let craft = config.parse(&args(&["--bin-path", "bin"])).unwrap();
+        assert!(!craft.main);
+        assert_eq!(craft.bin_path, "bin");
+
+        project.write(b"bin-path = \"bin\"\nlib-path = \"lib\"\n")?;
+        let config = CraftConfig::load(std::slice::from_ref(&project))?;
+        let craft = config.parse(&args(&["--main"])).unwrap();
+        assert!(craft.main);
+        assert_eq!(craft.bin_path, ".");
+        assert_eq!(craft.lib_path, None);
+
+        project.parent().unwrap().delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_paths_without_project_configuration() -> Result<()> {
+        let user = default_craft_home().join(CONFIG_FILE_NAME);
+        let directory = Path::raw("/");
+        assert!(
+            !directory.join(PROJECT_CONFIG_FILE_NAME).is_file(),
+            "a {PROJECT_CONFIG_FILE_NAME} at the root of the filesystem"
+        );
+        assert_eq!(CraftConfig::paths_from(&directory), vec![user.clone()]);
+
+        let member = test_path("config", "paths/member");
+        member.mkdir()?;
+        let project = member.parent().unwrap().join(PROJECT_CONFIG_FILE_NAME);
+        project.write(b"offline = true\n")?;
+        assert_eq!(
+            CraftConfig::paths_from(&member),
+            vec![user.clone(), project.clone()]
+        );
+        project.delete()?;
+        assert_eq!(CraftConfig::paths()[0], user);
+        member.parent().unwrap().delete()?;
+        Ok(())
+    }
+}
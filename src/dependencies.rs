@@ -1,18 +1,63 @@
+use crate::errors::{Error, Result};
 use crate::helpers::{
-    into_acceptable_package_name, to_pascal_case, valid_crate_name,
+    into_acceptable_package_name, to_pascal_case, valid_dependency_name, valid_target,
+    valid_version_req,
 };
+use crate::traceback;
 use clap::Parser;
 use std::cmp::PartialEq;
 use std::fmt::{Display, Formatter};
 use toml::{Table, Value};
 
+/// `KNOWN_ERROR_TYPES` maps crates to the paths of their error types,
+/// relative to the crate root, for which `errors.rs` gets a `From`
+/// impl, and to the `sysexits.h` exit code of the matching variant
+pub const KNOWN_ERROR_TYPES: [(&str, &[&str], u8); 28] = [
+    ("anyhow", &["Error"], 70),
+    ("base64", &["DecodeError"], 65),
+    ("chrono", &["ParseError"], 65),
+    ("clap", &["Error"], 64),
+    ("csv", &["Error"], 65),
+    ("git2", &["Error"], 74),
+    ("glob", &["PatternError", "GlobError"], 65),
+    ("hex", &["FromHexError"], 65),
+    ("http", &["Error"], 69),
+    ("hyper", &["Error"], 69),
+    ("image", &["ImageError"], 65),
+    ("quick_xml", &["Error"], 65),
+    ("regex", &["Error"], 65),
+    ("reqwest", &["Error"], 69),
+    ("rusqlite", &["Error"], 74),
+    ("semver", &["Error"], 65),
+    ("serde_json", &["Error"], 65),
+    ("serde_yaml", &["Error"], 65),
+    ("sqlx", &["Error"], 69),
+    ("tera", &["Error"], 65),
+    ("tokio", &["task::JoinError"], 70),
+    ("toml", &["de::Error", "ser::Error"], 65),
+    ("toml_edit", &["TomlError"], 65),
+    ("ureq", &["Error"], 69),
+    ("url", &["ParseError"], 65),
+    ("uuid", &["Error"], 65),
+    ("walkdir", &["Error"], 74),
+    ("zip", &["result::ZipError"], 74),
+];
 
-#[derive(Parser, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// `FEATURE_GATED_ERROR_TYPES` lists the error types of
+/// [`KNOWN_ERROR_TYPES`] which only exist when any of the given
+/// features of their crate is enabled
+pub const FEATURE_GATED_ERROR_TYPES: [(&str, &str, &[&str]); 1] =
+    [("tokio", "task::JoinError", &["rt", "full"])];
+
+#[derive(Parser, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Dependency {
-    #[arg(value_parser = valid_crate_name)]
+    #[arg(value_parser = valid_dependency_name, help = "crate name optionally followed by `@<version requirement>'")]
     pub name: String,
 
-    #[arg(short = 'F', long, value_delimiter=',')]
+    #[arg(long, value_parser = valid_version_req)]
+    pub version: Option<String>,
+
+    #[arg(short = 'F', long)]
     pub features: Option<String>,
 
     #[arg(long, conflicts_with = "build")]
@@ -23,10 +68,38 @@ pub struct Dependency {
 
     #[arg(long)]
     pub optional: bool,
+
+    #[arg(long)]
+    pub no_default_features: bool,
+
+    #[arg(long, value_parser = valid_target, help = "target triple or `cfg(...)' expression")]
+    pub target: Option<String>,
+
+    #[arg(long, conflicts_with = "git", help = "local path, resolved relative to the new crate")]
+    pub path: Option<String>,
+
+    #[arg(long)]
+    pub git: Option<String>,
+
+    #[arg(long, requires = "git", conflicts_with_all = ["tag", "rev"])]
+    pub branch: Option<String>,
+
+    #[arg(long, requires = "git", conflicts_with = "rev")]
+    pub tag: Option<String>,
+
+    #[arg(long, requires = "git")]
+    pub rev: Option<String>,
+
+    #[arg(long, value_parser = valid_dependency_name, help = "name under which the crate is imported")]
+    pub rename: Option<String>,
 }
 impl Display for Dependency {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        let mut args = vec![self.name.to_string()];
+        let mut args = vec![match &self.version {
+            // quoted so that requirements such as `>=1.2' reach cargo intact
+            Some(version) => format!("'{}@{}'", self.name, version),
+            None => self.name.to_string(),
+        }];
         if self.dev {
             args.push("--dev".to_string());
         }
@@ -36,15 +109,79 @@ impl Display for Dependency {
         if self.optional {
             args.push("--optional".to_string());
         }
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
         let features = self.features();
-        if features.len() > 0 {
+        if !features.is_empty() {
             args.push(format!("-F{}", features.join(",")));
         }
+        for (flag, value) in self.sources() {
+            args.push(format!("--{flag} '{value}'"));
+        }
+        if let Some(rename) = &self.rename {
+            args.push(format!("--rename {rename}"));
+        }
+        if let Some(target) = &self.target {
+            args.push(format!("--target '{target}'"));
+        }
         write!(f, "{}", args.join(" "))
     }
 }
 
 impl Dependency {
+    /// `parse` parses the specification given to `--dep`, e.g.:
+    /// `"serde_json@1.0.100 -F preserve_order"` or `"tokio --version ^1.38"`
+    pub fn parse(spec: impl Display) -> Result<Dependency> {
+        let spec = spec.to_string();
+        let args = ["dependency".to_string()]
+            .into_iter()
+            .chain(join_lists(split_spec(&spec)?));
+        let mut dependency =
+            Dependency::try_parse_from(args).map_err(|e| traceback!(ParseError, e))?;
+        if let Some((name, version)) = dependency.name.clone().split_once('@') {
+            if dependency.version.is_some() {
+                return Err(traceback!(
+                    ParseError,
+                    format!("{spec:#?} sets the version both with `@' and `--version'")
+                ));
+            }
+            dependency.name = name.to_string();
+            dependency.version = Some(version.to_string());
+        }
+        if let Some(rename) = dependency.rename.as_ref().filter(|name| name.contains('@')) {
+            return Err(traceback!(
+                ParseError,
+                format!("{rename:#?} is not a valid name for `--rename'")
+            ));
+        }
+        Ok(dependency)
+    }
+    /// `sources` lists the `--path`, `--git`, `--branch`, `--tag` and
+    /// `--rev` given to the dependency, in that order
+    pub fn sources(&self) -> Vec<(&'static str, String)> {
+        [
+            ("path", &self.path),
+            ("git", &self.git),
+            ("branch", &self.branch),
+            ("tag", &self.tag),
+            ("rev", &self.rev),
+        ]
+        .into_iter()
+        .filter_map(|(flag, value)| value.clone().map(|value| (flag, value)))
+        .collect()
+    }
+    /// `is_local_git` is true for `--git file://...`
+    pub fn is_local_git(&self) -> bool {
+        self.git
+            .as_deref()
+            .is_some_and(|git| git.starts_with("file://"))
+    }
+    /// `import_name` is the name under which the dependency is
+    /// imported, i.e.: `--rename` if given
+    pub fn import_name(&self) -> String {
+        self.rename.clone().unwrap_or_else(|| self.name.clone())
+    }
     pub fn features(&self) -> Vec<String> {
         let mut features = Vec::<String>::new();
         for h in self
@@ -52,7 +189,7 @@ impl Dependency {
             .clone()
             .unwrap_or_default()
             .split(",")
-            .filter(|h| h.trim().len() > 0)
+            .filter(|h| !h.trim().is_empty())
         {
             features.push(h.to_string());
         }
@@ -61,10 +198,19 @@ impl Dependency {
     pub fn to_tera(&self) -> Table {
         let mut dep = Table::new();
         dep.insert("name".to_string(), Value::String(self.name.to_string()));
+        if let Some(version) = &self.version {
+            dep.insert("version".to_string(), Value::String(version.to_string()));
+        }
         dep.insert(
             "package_name".to_string(),
-            Value::String(into_acceptable_package_name(self.name.as_str())),
+            Value::String(into_acceptable_package_name(self.import_name().as_str())),
         );
+        for (key, value) in self.sources() {
+            dep.insert(key.to_string(), Value::String(value));
+        }
+        if let Some(rename) = &self.rename {
+            dep.insert("rename".to_string(), Value::String(rename.to_string()));
+        }
         dep.insert(
             "features".to_string(),
             Value::Array(
@@ -77,9 +223,173 @@ impl Dependency {
         dep.insert("pascal_case".to_string(), Value::String(self.pascal_name()));
         dep.insert("dev".to_string(), Value::Boolean(self.dev));
         dep.insert("build".to_string(), Value::Boolean(self.build));
+        dep.insert(
+            "default_features".to_string(),
+            Value::Boolean(!self.no_default_features),
+        );
+        if let Some(target) = &self.target {
+            dep.insert("target".to_string(), Value::String(target.to_string()));
+        }
         dep
     }
     pub fn pascal_name(&self) -> String {
-        to_pascal_case(self.name.as_str())
+        to_pascal_case(self.import_name().replace('_', "-"))
+    }
+    /// `error_type_paths` lists the error types of the dependency
+    /// known to [`KNOWN_ERROR_TYPES`], leaving out dependencies that
+    /// the library cannot always refer to (dev, build, optional and
+    /// target-specific ones) as well as the error types of
+    /// [`FEATURE_GATED_ERROR_TYPES`] whose features are not enabled
+    pub fn error_type_paths(&self) -> Vec<String> {
+        if self.dev || self.build || self.optional || self.target.is_some() {
+            return Vec::new();
+        }
+        let name = self.name.replace('-', "_");
+        let import_name = self.import_name().replace('-', "_");
+        let features = self.features();
+        KNOWN_ERROR_TYPES
+            .iter()
+            .filter(|(known, _, _)| *known == name)
+            .flat_map(|(_, paths, _)| paths.iter())
+            .filter(|path| {
+                FEATURE_GATED_ERROR_TYPES
+                    .iter()
+                    .filter(|(known, gated, _)| *known == name && gated == *path)
+                    .all(|(_, _, gates)| {
+                        gates
+                            .iter()
+                            .any(|gate| features.iter().any(|f| f.trim() == *gate))
+                    })
+            })
+            .map(|path| format!("{import_name}::{path}"))
+            .collect()
+    }
+    /// `exit_code` is the exit code of the error variant derived from
+    /// the dependency, `EX_SOFTWARE` unless known otherwise
+    pub fn exit_code(&self) -> u8 {
+        let name = self.name.replace('-', "_");
+        KNOWN_ERROR_TYPES
+            .iter()
+            .find(|(known, _, _)| *known == name)
+            .map(|(_, _, exit_code)| *exit_code)
+            .unwrap_or(70)
+    }
+    /// `manifest_table` is the table of `Cargo.toml` listing the dependency
+    pub fn manifest_table(&self) -> &'static str {
+        if self.dev {
+            "dev-dependencies"
+        } else if self.build {
+            "build-dependencies"
+        } else {
+            "dependencies"
+        }
+    }
+    /// `manifest_entry` is the value written into `Cargo.toml` the
+    /// way `cargo add` would write it, e.g.:
+    /// `{ version = "1.0.213", features = ["derive"] }`
+    pub fn manifest_entry(&self, version: Option<&str>) -> toml_edit::Value {
+        let mut entry = toml_edit::InlineTable::new();
+        if let Some(version) = version {
+            entry.insert("version", version.into());
+        }
+        for (key, value) in self.sources() {
+            entry.insert(key, value.into());
+        }
+        if self.rename.is_some() {
+            entry.insert("package", self.name.as_str().into());
+        }
+        if self.no_default_features {
+            entry.insert("default-features", false.into());
+        }
+        let features = self.features();
+        if !features.is_empty() {
+            entry.insert("features", features.iter().collect::<toml_edit::Array>().into());
+        }
+        if self.optional {
+            entry.insert("optional", true.into());
+        }
+        match (entry.len(), version) {
+            (1, Some(version)) => version.into(),
+            _ => entry.into(),
+        }
+    }
+}
+
+/// `add_manifest_dependency` inserts `entry` as `name` into the
+/// `table` (e.g.: `dev-dependencies`) of `manifest`, under
+/// `[target.<target>]` when given
+pub fn add_manifest_dependency(
+    manifest: &mut toml_edit::DocumentMut,
+    target: Option<&str>,
+    table: &str,
+    name: &str,
+    entry: toml_edit::Value,
+) -> Result<()> {
+    let mut parent = manifest.as_table_mut();
+    if let Some(target) = target {
+        // `cargo add` writes `[target.'cfg(unix)'.dependencies]`
+        let literal = format!("'{target}'")
+            .parse::<toml_edit::Key>()
+            .unwrap_or_else(|_| toml_edit::Key::new(target));
+        for key in [toml_edit::Key::new("target"), literal] {
+            parent = parent
+                .entry_format(&key)
+                .or_insert_with(|| {
+                    let mut table = toml_edit::Table::new();
+                    table.set_implicit(true);
+                    toml_edit::Item::Table(table)
+                })
+                .as_table_mut()
+                .ok_or_else(|| Error::DeserializationError(format!("`{key}' is not a table")))?;
+        }
+    }
+    parent
+        .entry(table)
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .ok_or_else(|| Error::DeserializationError(format!("`{table}' is not a table")))?
+        .insert(name, toml_edit::Item::Value(entry));
+    Ok(())
+}
+
+/// `split_spec` splits a dependency specification on whitespace
+/// except within single or double quotes, e.g.:
+/// `libc --target 'cfg(target_os = "linux")'`
+fn split_spec(spec: &str) -> Result<Vec<String>> {
+    let mut args = Vec::<String>::new();
+    let mut arg: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in spec.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => arg.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                arg.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(arg.take()),
+            (None, c) => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(traceback!(
+            ParseError,
+            format!("unterminated quote in {spec:#?}")
+        ));
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+/// `join_lists` joins every argument ending with a comma to the next
+/// one, as in `tokio@>=1.38, <2` or `-F derive, rc`
+fn join_lists(args: Vec<String>) -> Vec<String> {
+    let mut joined = Vec::<String>::new();
+    for arg in args {
+        match joined.last_mut() {
+            Some(last) if last.ends_with(',') => last.push_str(&arg),
+            _ => joined.push(arg),
+        }
     }
+    joined
 }
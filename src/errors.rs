@@ -49,6 +49,17 @@ impl Error {
         }
         .to_string()
     }
+    /// `exit_code` follows the conventions of `sysexits.h` so that
+    /// scripts can tell invalid input from failures of cargo-craft
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::ParseError(_) => 64,
+            Error::DeserializationError(_) | Error::TemplateError(_) | Error::JsonError(_) => 65,
+            Error::ShellCommandError(_) => 69,
+            Error::SerializationError(_) | Error::RuntimeError(_) => 70,
+            Error::IOError(_) => 74,
+        }
+    }
 }
 
 impl std::error::Error for Error {}
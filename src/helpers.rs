@@ -1,3 +1,5 @@
+use crate::cfg::Cfg;
+use chrono::NaiveDate;
 use iocore::Path;
 use regex::Regex;
 use toml::{Table, Value};
@@ -5,7 +7,7 @@ use toml::{Table, Value};
 pub fn extend_table(from: &Table, into: &Table) -> Table {
     let mut extended = into.clone();
     for (k, v) in from.iter() {
-        extended.insert(k.clone(), v.clone().into());
+        extended.insert(k.clone(), v.clone());
     }
     extended
 }
@@ -35,11 +37,10 @@ pub fn strip_ends(text: &str, sep: &str) -> String {
         .strip_prefix(sep)
         .map(|text| text.to_string())
         .unwrap_or_else(|| text.to_string());
-    let text = text
+    text
         .strip_suffix(sep)
         .map(|text| text.to_string())
-        .unwrap_or_else(|| text.to_string());
-    text
+        .unwrap_or_else(|| text.to_string())
 }
 
 pub fn acceptable_crate_name(val: &str) -> ::std::result::Result<String, String> {
@@ -77,11 +78,14 @@ pub fn path_to_entry_path(entry: Option<Table>) -> Option<Path> {
     }
 }
 
+#[deprecated(
+    note = "validates the whole path as a crate name, `Craft::validate_path` checks the name of <AT> instead"
+)]
 pub fn valid_manifest_path(val: &str) -> ::std::result::Result<Path, String> {
     let val = acceptable_crate_name(val)?;
     let path = Path::new(val);
     let path = if path.name() == "Cargo.toml" && !path.is_dir() {
-        path.parent().expect(&format!("parent of {}", &path))
+        path.parent().unwrap_or_else(|| panic!("parent of {}", &path))
     } else {
         path.clone()
     };
@@ -92,6 +96,44 @@ pub fn valid_manifest_path(val: &str) -> ::std::result::Result<Path, String> {
     Ok(path)
 }
 
+pub fn valid_workspace_root(val: &str) -> ::std::result::Result<Path, String> {
+    let path = Path::new(val).try_canonicalize();
+    let path = if path.name() == "Cargo.toml" && path.is_file() {
+        path.parent()
+            .ok_or_else(|| format!("could not find parent path of `{val}'"))?
+    } else {
+        path
+    };
+    let manifest_path = path.join("Cargo.toml");
+    let manifest = manifest_path
+        .read()
+        .map_err(|error| format!("could not read {manifest_path}: {error}"))?
+        .parse::<Table>()
+        .map_err(|error| format!("could not parse {manifest_path}: {error}"))?;
+    if !manifest.get("workspace").map(Value::is_table).unwrap_or(false) {
+        return Err(format!("{manifest_path} does not declare a [workspace]"));
+    }
+    Ok(path)
+}
+
+pub fn existing_directory(val: &str) -> ::std::result::Result<Path, String> {
+    let path = Path::new(val).try_canonicalize();
+    if path.is_dir() {
+        Ok(path)
+    } else {
+        Err(format!("{val:#?} is not a directory"))
+    }
+}
+
+pub fn existing_file(val: &str) -> ::std::result::Result<Path, String> {
+    let path = Path::new(val).try_canonicalize();
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(format!("{val:#?} is not a file"))
+    }
+}
+
 pub fn absolute_path(val: &str) -> ::std::result::Result<Path, String> {
     let path = Path::new(val);
     let parent = path
@@ -103,6 +145,44 @@ pub fn absolute_path(val: &str) -> ::std::result::Result<Path, String> {
         .join(path.name()))
 }
 
+/// `cargo_home` is `$CARGO_HOME` or `~/.cargo`
+pub fn cargo_home() -> Path {
+    let cargo_home = std::env::var("CARGO_HOME").unwrap_or_else(|_| "~/.cargo".to_string());
+    Path::new(cargo_home).try_canonicalize()
+}
+
+/// `default_craft_home` is the directory holding the history and the
+/// receipts of cargo-craft: `$CARGO_CRAFT_HOME` or [`cargo_home`]
+pub fn default_craft_home() -> Path {
+    match std::env::var("CARGO_CRAFT_HOME") {
+        Ok(craft_home) if !craft_home.is_empty() => Path::new(craft_home).try_canonicalize(),
+        _ => cargo_home(),
+    }
+}
+
+/// `relative_path` returns `to` relative to the directory `from`,
+/// both of which are made absolute from the current directory
+pub fn relative_path(from: impl std::fmt::Display, to: impl std::fmt::Display) -> String {
+    let absolute = |path: String| -> Vec<String> {
+        std::path::absolute(&path)
+            .unwrap_or_else(|_| std::path::PathBuf::from(&path))
+            .components()
+            .filter(|component| !matches!(component, std::path::Component::CurDir))
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect()
+    };
+    let (from, to) = (absolute(from.to_string()), absolute(to.to_string()));
+    let common = from.iter().zip(to.iter()).take_while(|(a, b)| a == b).count();
+    let parts = std::iter::repeat_n("..".to_string(), from.len() - common)
+        .chain(to[common..].iter().cloned())
+        .collect::<Vec<String>>();
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
 pub fn crate_name_from_path(path: impl Into<Path>) -> ::std::result::Result<String, String> {
     let name = path.into().without_extension().name();
     let crate_name = into_acceptable_crate_name(&name);
@@ -138,8 +218,7 @@ pub fn into_acceptable_name(val: &str, sep: char) -> String {
     let re = Regex::new(&format!(r"[{}]+", sep)).unwrap();
     let val = re.replace_all(&val, String::from(sep)).to_string();
     let re = Regex::new(&format!(r"[^a-zA-Z0-9{}]", sep)).unwrap();
-    let val = re.replace_all(&val, String::from(sep)).to_string();
-    val
+    re.replace_all(&val, String::from(sep)).to_string()
 }
 
 /// `capitalize_string`
@@ -156,7 +235,7 @@ pub fn capitalize_string(val: impl std::fmt::Display) -> String {
     match val.len() {
         0 => val,
         1 => val.to_uppercase(),
-        _ => format!("{}{}", val[0..1].to_uppercase(), val[1..].to_string()),
+        _ => format!("{}{}", val[0..1].to_uppercase(), &val[1..]),
     }
 }
 pub fn struct_name_from_package_name(val: impl std::fmt::Display) -> String {
@@ -164,15 +243,47 @@ pub fn struct_name_from_package_name(val: impl std::fmt::Display) -> String {
     let package_name = into_acceptable_package_name(&val);
     package_name
         .split("_")
-        .map(|part| capitalize_string(part))
+        .map(capitalize_string)
         .collect::<String>()
 }
 
 pub fn valid_crate_name(val: &str) -> ::std::result::Result<String, String> {
-    Ok(
-        acceptable_crate_name(into_acceptable_name(val, '-').as_str())
-            .map_err(|_| format!("{:#?} is not a valid crate name", val))?,
-    )
+    acceptable_crate_name(into_acceptable_name(val, '-').as_str())
+        .map_err(|_| format!("{:#?} is not a valid crate name", val))
+}
+pub fn valid_version_req(val: &str) -> ::std::result::Result<String, String> {
+    semver::VersionReq::parse(val)
+        .map(|_| val.to_string())
+        .map_err(|error| format!("{:#?} is not a valid version requirement: {}", val, error))
+}
+/// `valid_dependency_name` accepts either `name` or
+/// `name@version-requirement`, keeping the name as spelled on
+/// crates.io since `cargo add name@req` does not correct it
+pub fn valid_dependency_name(val: &str) -> ::std::result::Result<String, String> {
+    let (name, req) = match val.split_once('@') {
+        Some((name, req)) => (name, Some(valid_version_req(req)?)),
+        None => (val, None),
+    };
+    let re = Regex::new(r"^[a-zA-Z][a-zA-Z0-9_-]*$").unwrap();
+    if !re.is_match(name) {
+        return Err(format!("{:#?} is not a valid crate name", name));
+    }
+    Ok(match req {
+        Some(req) => format!("{name}@{req}"),
+        None => name.to_string(),
+    })
+}
+/// `valid_target` accepts either a target triple or a `cfg(...)` expression
+pub fn valid_target(val: &str) -> ::std::result::Result<String, String> {
+    if val.starts_with("cfg") {
+        Cfg::parse(val)
+            .map(|_| val.to_string())
+            .map_err(|error| format!("{:#?} is not a valid cfg expression: {}", val, error))
+    } else if Regex::new(r"^[a-zA-Z0-9_.-]+$").unwrap().is_match(val) {
+        Ok(val.to_string())
+    } else {
+        Err(format!("{:#?} is neither a target triple nor a cfg expression", val))
+    }
 }
 pub fn to_pascal_case(val: impl std::fmt::Display) -> String {
     let pattern = regex::Regex::new(r"\W+").unwrap();
@@ -182,17 +293,97 @@ pub fn to_pascal_case(val: impl std::fmt::Display) -> String {
         .collect()
 }
 pub fn words(val: impl std::fmt::Display) -> Vec<String> {
-    let pattern = regex::Regex::new(r"\b\W+\b").unwrap();
+    let pattern = regex::Regex::new(r"[a-zA-Z0-9]+").unwrap();
     pattern
         .find_iter(val.to_string().as_str())
         .map(|h| h.as_str().to_string())
         .collect()
 }
+/// `valid_error_type` accepts `Name`, `Name=path::to::Error`, which
+/// generates a `From<path::to::Error>` impl, either of them optionally
+/// followed by `@<exit code>`, e.g.: `Input=std::num::ParseIntError@dataerr`
+pub fn valid_error_type(val: &str) -> ::std::result::Result<String, String> {
+    let (name, path, exit_code) = split_error_type(val);
+    if into_acceptable_error_type_name(&name).is_empty() {
+        return Err(format!("{:#?} is not a valid error type name", name));
+    }
+    if let Some(path) = path {
+        let re = Regex::new(r"^(::)?[a-zA-Z_][a-zA-Z0-9_]*(::[a-zA-Z_][a-zA-Z0-9_]*)*$").unwrap();
+        if !re.is_match(&path) {
+            return Err(format!("{:#?} is not a valid type path", path));
+        }
+    }
+    if let Some(exit_code) = exit_code {
+        valid_exit_code(&exit_code)?;
+    }
+    Ok(val.to_string())
+}
+/// `split_error_type` splits `Name=path::to::Error@code` into its
+/// name, path and exit code
+pub fn split_error_type(val: &str) -> (String, Option<String>, Option<String>) {
+    let (val, exit_code) = match val.rsplit_once('@') {
+        Some((val, exit_code)) => (val, Some(exit_code.trim().to_string())),
+        None => (val, None),
+    };
+    match val.split_once('=') {
+        Some((name, path)) => (
+            name.trim().to_string(),
+            Some(path.trim().to_string()),
+            exit_code,
+        ),
+        None => (val.trim().to_string(), None, exit_code),
+    }
+}
+/// `SYSEXITS` maps the names of the exit codes of `sysexits.h`,
+/// without their `EX_` prefix, to their values
+pub const SYSEXITS: [(&str, u8); 15] = [
+    ("usage", 64),
+    ("dataerr", 65),
+    ("noinput", 66),
+    ("nouser", 67),
+    ("nohost", 68),
+    ("unavailable", 69),
+    ("software", 70),
+    ("oserr", 71),
+    ("osfile", 72),
+    ("cantcreat", 73),
+    ("ioerr", 74),
+    ("tempfail", 75),
+    ("protocol", 76),
+    ("noperm", 77),
+    ("config", 78),
+];
+/// `valid_exit_code` accepts either a number between 1 and 255 or
+/// the name of a `sysexits.h` exit code, e.g.: `dataerr` or `EX_DATAERR`
+pub fn valid_exit_code(val: &str) -> ::std::result::Result<u8, String> {
+    let name = val.to_lowercase();
+    let name = name.strip_prefix("ex_").unwrap_or(&name);
+    match SYSEXITS.iter().find(|(known, _)| *known == name) {
+        Some((_, code)) => Ok(*code),
+        None => match val.parse::<u8>() {
+            Ok(code) if code > 0 => Ok(code),
+            _ => Err(format!(
+                "{:#?} is neither an exit code between 1 and 255 nor one of {}",
+                val,
+                SYSEXITS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )),
+        },
+    }
+}
+/// `valid_date` accepts dates as in `2025-01-31`
+pub fn valid_date(val: &str) -> ::std::result::Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(val, "%Y-%m-%d")
+        .map_err(|error| format!("{:#?} is not a date as in `2025-01-31': {}", val, error))
+}
 pub fn into_acceptable_error_type_name(val: &str) -> String {
-    let pattern = regex::Regex::new(r"(?i)^(?<name>.*)(?:Error)?$").unwrap();
+    let pattern = regex::Regex::new(r"(?i)^(?<name>.*?)(?:Error)?$").unwrap();
     words(pattern.replace_all(val, "$name"))
         .iter()
-        .map(|h| capitalize_string(h))
+        .map(capitalize_string)
         .collect::<Vec<String>>()
         .join("")
 }
@@ -230,9 +421,90 @@ mod tests {
         Ok(())
     }
     #[test]
+    fn test_valid_dependency_name() {
+        assert_eq!(valid_dependency_name("serde_json"), Ok(String::from("serde_json")));
+        assert_eq!(
+            valid_dependency_name("serde_json@1.0.100"),
+            Ok(String::from("serde_json@1.0.100"))
+        );
+        assert_eq!(
+            valid_dependency_name("serde json"),
+            Err(String::from("\"serde json\" is not a valid crate name"))
+        );
+        assert_eq!(
+            valid_dependency_name("tokio@>=1.38, <2"),
+            Ok(String::from("tokio@>=1.38, <2"))
+        );
+        assert_eq!(
+            valid_dependency_name("tokio@latest"),
+            Err(String::from(
+                "\"latest\" is not a valid version requirement: unexpected character 'l' while parsing major version number"
+            ))
+        );
+    }
+    #[test]
     fn test_struct_name_from_package_name() -> Result<(), String> {
         let struct_name = struct_name_from_package_name("package_name");
         assert_eq!(struct_name, "PackageName");
         Ok(())
     }
+    #[test]
+    fn test_words() {
+        // `\b\W+\b' used to match the separators between words, e.g.
+        // `[" "]' for "my thing" and nothing at all for "serde_json"
+        assert_eq!(words("my thing"), vec!["my", "thing"]);
+        assert_eq!(words("serde_json::Error"), vec!["serde", "json", "Error"]);
+        assert!(words(" -- ").is_empty());
+        // the greedy `.*' used to swallow the `Error' suffix
+        assert_eq!(into_acceptable_error_type_name("TomlError"), "Toml");
+        assert_eq!(into_acceptable_error_type_name("Error"), "");
+    }
+    #[test]
+    fn test_into_acceptable_error_type_name() {
+        assert_eq!(into_acceptable_error_type_name("Json"), "Json");
+        assert_eq!(into_acceptable_error_type_name("JsonError"), "Json");
+        assert_eq!(into_acceptable_error_type_name("serde_json"), "SerdeJson");
+        assert_eq!(into_acceptable_error_type_name("my thing"), "MyThing");
+    }
+    #[test]
+    fn test_valid_error_type() {
+        assert_eq!(valid_error_type("Json"), Ok("Json".to_string()));
+        assert_eq!(
+            valid_error_type("Toml=toml::de::Error"),
+            Ok("Toml=toml::de::Error".to_string())
+        );
+        assert_eq!(
+            split_error_type("Toml = toml::de::Error"),
+            ("Toml".to_string(), Some("toml::de::Error".to_string()), None)
+        );
+        assert_eq!(
+            split_error_type("Input=std::num::ParseIntError@dataerr"),
+            (
+                "Input".to_string(),
+                Some("std::num::ParseIntError".to_string()),
+                Some("dataerr".to_string())
+            )
+        );
+        assert_eq!(
+            split_error_type("Input@65"),
+            ("Input".to_string(), None, Some("65".to_string()))
+        );
+        assert!(valid_error_type("Toml=toml::de::Error<T>").is_err());
+        assert!(valid_error_type("=toml::de::Error").is_err());
+        assert!(valid_error_type("Input@nope").is_err());
+    }
+    #[test]
+    fn test_valid_exit_code() {
+        assert_eq!(valid_exit_code("dataerr"), Ok(65));
+        assert_eq!(valid_exit_code("EX_USAGE"), Ok(64));
+        assert_eq!(valid_exit_code("3"), Ok(3));
+        assert!(valid_exit_code("0").is_err());
+        assert!(valid_exit_code("256").is_err());
+    }
+    #[test]
+    fn test_valid_date() {
+        assert_eq!(valid_date("2025-01-31"), Ok(NaiveDate::from_ymd_opt(2025, 1, 31).unwrap()));
+        assert!(valid_date("2025-02-30").is_err());
+        assert!(valid_date("31/01/2025").is_err());
+    }
 }
@@ -0,0 +1,325 @@
+use crate::cli::Craft;
+use crate::config::CraftConfig;
+use crate::dependencies::Dependency;
+use crate::errors::{Error, Result};
+use crate::helpers::{
+    valid_crate_name, valid_error_type, valid_package_name, valid_subcommand_name,
+};
+use crate::plan::PlanFormat;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::CommandFactory;
+use iocore::Path;
+use std::io::{BufRead, Write};
+
+/// `Interview` completes the command-line of [`Craft`] with the
+/// answers to questions about the new crate, leaving out the
+/// questions about options given on the command-line, then shows the
+/// resulting command-line along with its plan before proceeding
+#[derive(Debug)]
+pub struct Interview<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Interview<R, W> {
+    pub fn new(input: R, output: W) -> Interview<R, W> {
+        Interview { input, output }
+    }
+    fn answer(&mut self, question: &str) -> Result<String> {
+        write!(self.output, "{question}: ")?;
+        self.output.flush()?;
+        let mut answer = String::new();
+        if self.input.read_line(&mut answer)? == 0 {
+            return Err(Error::IOError(format!(
+                "the input ended before answering {question:#?}"
+            )));
+        }
+        Ok(answer.trim().to_string())
+    }
+    /// `ask` asks `question` again until `validate` accepts the answer
+    fn ask<T>(
+        &mut self,
+        question: &str,
+        validate: impl Fn(&str) -> std::result::Result<T, String>,
+    ) -> Result<T> {
+        loop {
+            let answer = self.answer(question)?;
+            match validate(&answer) {
+                Ok(value) => return Ok(value),
+                Err(error) => writeln!(self.output, "{error}")?,
+            }
+        }
+    }
+    fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        let choices = if default { "Y/n" } else { "y/N" };
+        self.ask(&format!("{question} [{choices}]"), |answer| {
+            match answer.to_lowercase().as_str() {
+                "" => Ok(default),
+                "y" | "yes" => Ok(true),
+                "n" | "no" => Ok(false),
+                _ => Err(format!("{answer:#?} is neither `y' nor `n'")),
+            }
+        })
+    }
+    /// `ask_list` asks for comma-separated values, each of which
+    /// `validate` must accept
+    fn ask_list(
+        &mut self,
+        question: &str,
+        validate: fn(&str) -> std::result::Result<String, String>,
+    ) -> Result<Vec<String>> {
+        self.ask(question, |answer| {
+            answer
+                .split(',')
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(validate)
+                .collect()
+        })
+    }
+    /// `command_line` returns `args` along with the options answered
+    pub fn command_line(&mut self, args: &[String]) -> Result<Vec<String>> {
+        let matches = Craft::command()
+            .ignore_errors(true)
+            .try_get_matches_from(args)?;
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let bare = matches.get_flag("cli_barebones");
+        let mut args = args
+            .iter()
+            .filter(|arg| !["-i", "--interactive"].contains(&arg.as_str()))
+            .cloned()
+            .collect::<Vec<String>>();
+
+        if !given("at") {
+            let name = self.ask("crate name", valid_crate_name)?;
+            args.insert(1.min(args.len()), Path::cwd().join(name).to_string());
+        }
+        if !given("package_name") {
+            let question = "package name (empty for the crate name)";
+            let package_name = self.ask(question, |answer| match answer {
+                "" => Ok(None),
+                answer => valid_package_name(answer).map(Some),
+            })?;
+            if let Some(package_name) = package_name {
+                args.extend(["--package-name".to_string(), package_name]);
+            }
+        }
+        if !given("description") {
+            let description = self.answer("description (optional)")?;
+            if !description.is_empty() {
+                args.extend(["--description".to_string(), description]);
+            }
+        }
+        let cli = if given("cli") {
+            matches.get_flag("cli")
+        } else {
+            let cli = self.confirm("command-line application?", false)?;
+            if cli {
+                args.push("--cli".to_string());
+            }
+            cli
+        };
+        if cli && !bare && !given("subcommand_names") {
+            let question = "subcommands (comma-separated, empty for none)";
+            let names = self.ask_list(question, valid_subcommand_name)?;
+            if !names.is_empty() {
+                if !given("subcommands") {
+                    args.push("--subcommands".to_string());
+                }
+                args.extend(["--subcommand".to_string(), names.join(",")]);
+            }
+        }
+        if !bare && !given("bin") {
+            let question = "binaries (comma-separated, empty for the default one)";
+            let bins = self.ask_list(question, valid_crate_name)?;
+            if !bins.is_empty() {
+                args.extend(["--bin".to_string(), bins.join(",")]);
+            }
+        }
+        if !given("dep") {
+            let question = "dependency (as with `--dep', empty to finish)";
+            while let Some(dep) = self.ask(question, |answer| match answer {
+                "" => Ok(None),
+                answer => Dependency::parse(answer)
+                    .map(|_| Some(answer.to_string()))
+                    .map_err(|error| error.to_string()),
+            })? {
+                args.extend(["--dep".to_string(), dep]);
+            }
+        }
+        if !bare && !given("add_error_type") {
+            let question = "error types (comma-separated, as with `--add-error-type')";
+            for error_type in self.ask_list(question, valid_error_type)? {
+                args.extend(["--add-error-type".to_string(), error_type]);
+            }
+        }
+        Ok(args)
+    }
+    /// `craft` interviews the user then returns the [`Craft`] of the
+    /// resulting command-line once the user agrees with its plan
+    pub fn craft(
+        &mut self,
+        config: &CraftConfig,
+        args: &[String],
+    ) -> std::result::Result<Craft, clap::Error> {
+        let mut command = Craft::command();
+        let args = self
+            .command_line(args)
+            .map_err(|error| command.error(ErrorKind::Io, error))?;
+        let craft = config.parse(&args)?;
+        self.review(&args, &craft)
+            .map_err(|error| command.error(ErrorKind::Io, error))?;
+        Ok(craft)
+    }
+    fn review(&mut self, args: &[String], craft: &Craft) -> Result<()> {
+        let command_line = args
+            .iter()
+            .skip(1)
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<String>>()
+            .join(" ");
+        writeln!(self.output, "\ncargo craft {command_line}\n")?;
+        // the plan is printed by `Craft::go' in the first place
+        if craft.dry_run.is_some() {
+            return Ok(());
+        }
+        write!(self.output, "{}", craft.plan()?.format(PlanFormat::Human)?)?;
+        if self.confirm("proceed?", true)? {
+            Ok(())
+        } else {
+            Err(Error::RuntimeError(
+                "cancelled: nothing was generated".to_string(),
+            ))
+        }
+    }
+}
+
+fn shell_quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./=,@:+".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn interview(answers: &str) -> Interview<Cursor<Vec<u8>>, Vec<u8>> {
+        Interview::new(Cursor::new(answers.as_bytes().to_vec()), Vec::new())
+    }
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+    #[test]
+    fn test_interview_command_line() -> Result<()> {
+        let mut library = interview("!!!\nlibrary\n\nparses things\n\n\nregex@1\n\n\n");
+        let command_line = library.command_line(&args(&["cargo-craft", "--interactive"]))?;
+        assert_eq!(
+            command_line,
+            args(&[
+                "cargo-craft",
+                &Path::cwd().join("library").to_string(),
+                "--description",
+                "parses things",
+                "--dep",
+                "regex@1",
+            ])
+        );
+        let output = String::from_utf8(library.output.clone()).unwrap();
+        assert!(
+            output.contains("\"!!!\" is not a valid crate name"),
+            "{output}"
+        );
+
+        let mut cli = interview("tool_kit\n\ny\ninit, sync\ntk\nserde_json\nreqwest -F blocking\n\nInput=std::num::ParseIntError\n");
+        let command_line =
+            cli.command_line(&args(&["cargo-craft", "-i", "/tmp/toolkit", "--offline"]))?;
+        assert_eq!(
+            command_line,
+            args(&[
+                "cargo-craft",
+                "/tmp/toolkit",
+                "--offline",
+                "--package-name",
+                "tool_kit",
+                "--cli",
+                "--subcommands",
+                "--subcommand",
+                "init,sync",
+                "--bin",
+                "tk",
+                "--dep",
+                "serde_json",
+                "--dep",
+                "reqwest -F blocking",
+                "--add-error-type",
+                "Input=std::num::ParseIntError",
+            ])
+        );
+        let craft = CraftConfig::default().parse(&command_line).unwrap();
+        assert_eq!(craft.subcommand_names, vec!["init", "sync"]);
+        assert_eq!(craft.dep, vec!["serde_json", "reqwest -F blocking"]);
+
+        // options of the command-line are not asked about
+        let mut bare = interview("");
+        assert_eq!(
+            bare.command_line(&args(&[
+                "cargo-craft",
+                "-i",
+                "/tmp/bare",
+                "-P",
+                "bare",
+                "--description",
+                "",
+                "-c",
+                "--bare",
+                "-d",
+                "regex"
+            ]))?
+            .len(),
+            10
+        );
+        assert!(interview("")
+            .command_line(&args(&["cargo-craft", "-i"]))
+            .is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_interview_review() -> Result<()> {
+        let answers = "\nn\n\n\n\n";
+        let args = args(&[
+            "cargo-craft",
+            "-i",
+            "/tmp/reviewed",
+            "--description",
+            "a reviewed crate",
+            "--verify",
+            "none",
+        ]);
+        let mut declined = interview(&format!("{answers}n\n"));
+        let error = declined.craft(&CraftConfig::default(), &args).unwrap_err();
+        assert!(error.to_string().contains("cancelled"), "{error}");
+        let output = String::from_utf8(declined.output).unwrap();
+        assert!(
+            output.contains(
+                "\ncargo craft /tmp/reviewed --description 'a reviewed crate' --verify none\n"
+            ),
+            "{output}"
+        );
+        assert!(
+            output.contains("write /tmp/reviewed/Cargo.toml"),
+            "{output}"
+        );
+
+        let mut accepted = interview(&format!("{answers}\n"));
+        let craft = accepted.craft(&CraftConfig::default(), &args).unwrap();
+        assert_eq!(craft.at, Path::new("/tmp/reviewed"));
+        assert!(!craft.cli);
+        Ok(())
+    }
+}
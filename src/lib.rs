@@ -3,19 +3,69 @@ pub(crate) mod errors;
 pub(crate) mod helpers;
 
 pub use crate::helpers::{
-    absolute_path, acceptable_crate_name, capitalize_string, crate_name_from_path, extend_table,
-    into_acceptable_crate_name, into_acceptable_error_type_name, into_acceptable_name,
-    into_acceptable_package_name, package_name_from_string_or_path, path_to_entry_path, slug,
-    strip_ends, struct_name_from_package_name, to_pascal_case, valid_crate_name,
-    valid_manifest_path, valid_package_name, valid_subcommand_name, words,
+    absolute_path, acceptable_crate_name, capitalize_string, cargo_home, crate_name_from_path,
+    default_craft_home, existing_directory, existing_file, extend_table, into_acceptable_crate_name,
+    into_acceptable_error_type_name, into_acceptable_name, into_acceptable_package_name,
+    package_name_from_string_or_path, path_to_entry_path, relative_path, slug, split_error_type,
+    strip_ends, struct_name_from_package_name, to_pascal_case, valid_crate_name, valid_date,
+    valid_dependency_name, valid_error_type, valid_exit_code, valid_package_name,
+    valid_subcommand_name, valid_target, valid_version_req, valid_workspace_root, words, SYSEXITS,
 };
-pub use cli::{ClapExecuter, Craft};
+#[allow(deprecated)]
+pub use crate::helpers::valid_manifest_path;
+pub use cli::{ClapExecuter, Craft, HISTORY_FILE_NAME, RECEIPTS_FILE_NAME};
 pub use errors::{Error, ExecutionResult, Result};
 
 pub(crate) mod templates;
-pub use templates::{render, render_cli, render_info_string, tera, tera_info};
+pub use templates::{
+    render, render_cli, render_info_string, tera, tera_info, ErrorStyle, EMBEDDED_TEMPLATES,
+};
+
+pub(crate) mod workspace;
+pub use workspace::{
+    add_workspace_member, hoist_dependencies, hoist_workspace_dependencies,
+    WORKSPACE_DEPENDENCY_KEYS, WORKSPACE_INHERITABLE_KEYS,
+};
+
+pub(crate) mod pack;
+pub use pack::{PackTemplate, TemplatePack, PACK_MANIFEST_NAME};
+
+pub(crate) mod plan;
+pub use plan::{Plan, PlanFormat, Step, VerifyStep};
+
+pub(crate) mod runner;
+pub use runner::{CommandRunner, RecordingRunner, Runner, ShellRunner};
+
+pub(crate) mod sink;
+pub use sink::{DiskSink, FileSink, MemorySink, Sink};
+
+pub(crate) mod builder;
+pub use builder::CraftBuilder;
+
+pub(crate) mod cfg;
+pub use cfg::Cfg;
+
+pub(crate) mod commands;
+pub use commands::{
+    CraftCommand, HistoryEntry, HistoryQuery, ListFormat, QueryFilter, ReceiptEntry, ReceiptQuery,
+};
+
+pub(crate) mod config;
+pub use config::{CraftConfig, CONFIG_FILE_NAME, PROJECT_CONFIG_FILE_NAME};
+
+pub(crate) mod interactive;
+pub use interactive::Interview;
+
+pub(crate) mod receipts;
+pub use receipts::{
+    decode_receipt, encode_receipt, ReceiptParseErrors, ReceiptStore, RECEIPT_MIGRATIONS,
+    RECEIPT_SCHEMA_VERSION,
+};
+
+pub(crate) mod resolver;
+pub use resolver::VersionResolver;
 
 #[doc(hidden)]
 pub(crate) mod dependencies;
 #[doc(inline)]
-pub use dependencies::Dependency;
+pub use dependencies::{Dependency, FEATURE_GATED_ERROR_TYPES, KNOWN_ERROR_TYPES};
@@ -1,17 +1,24 @@
 use cargo_craft::{
     Craft,
     ClapExecuter,
+    CraftCommand,
     ExecutionResult::{Err, Ok},
 };
 
 fn main() {
+    if let Some(command) = CraftCommand::detect(&Craft::args()) {
+        return command.run().unwrap_or_else(|error| {
+            eprintln!("{error}");
+            std::process::exit(error.exit_code().into())
+        });
+    }
     match Craft::main() {
         Ok(_) => {}
         Err(mut receipt, error) => {
             receipt.runtime_errors.push(error.clone());
             receipt.write_receipt().unwrap_or_default();
             eprintln!("{error}");
-            std::process::exit(101);
+            std::process::exit(error.exit_code().into());
         }
     }
 }
@@ -0,0 +1,154 @@
+use crate::cli::Craft;
+use crate::errors::{Error, Result};
+use crate::templates::tera;
+use crate::traceback;
+use iocore::Path;
+use serde::{Deserialize, Serialize};
+use tera::{Context, Tera};
+use toml::{Table, Value};
+
+pub const PACK_MANIFEST_NAME: &str = "craft-pack.toml";
+
+/// `TemplatePack` is a directory containing a `craft-pack.toml`
+/// manifest which lists the templates rendered by
+/// [`Craft::render_templates`] in place of the built-in ones.
+///
+/// Example `craft-pack.toml`:
+/// ```toml
+/// name = "cargo-plugin"
+///
+/// [context]
+/// team = "platform"
+///
+/// [[template]]
+/// template = "lib.rs"   # an embedded template or a file in the pack directory
+/// output = "{{ lib_path }}/lib.rs"
+///
+/// [[template]]
+/// template = "cli"
+/// output = "{{ entry.path }}"
+/// each = "crate_binaries"
+/// when = ["craft_cli", "!craft_subcommands"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplatePack {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub context: Table,
+    #[serde(default, rename = "template")]
+    pub templates: Vec<PackTemplate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackTemplate {
+    /// file name relative to the pack directory or name of an embedded template
+    pub template: String,
+    /// tera string rendered into the output path relative to the new crate
+    pub output: String,
+    /// names of context variables which must all be truthy, prefixed with `!` to negate
+    #[serde(default)]
+    pub when: Vec<String>,
+    /// name of a context array to render the template once per item, exposed as `entry`
+    #[serde(default)]
+    pub each: Option<String>,
+}
+
+impl TemplatePack {
+    pub fn load(path: &Path) -> Result<TemplatePack> {
+        let manifest_path = path.join(PACK_MANIFEST_NAME);
+        if !manifest_path.is_file() {
+            return Err(Error::IOError(format!("{manifest_path} does not exist")));
+        }
+        toml::from_str::<TemplatePack>(&manifest_path.read()?)
+            .map_err(|error| traceback!(DeserializationError, format!("{manifest_path}: {error}")))
+    }
+    /// `register` adds the templates found in the pack directory to `tera`
+    /// and the pack context variables to `context` without overriding
+    /// the variables provided by cargo-craft
+    pub fn register(&self, path: &Path, tera: &mut Tera, context: &mut Context) -> Result<()> {
+        for template in self.templates.iter() {
+            let template_path = path.join(&template.template);
+            if template_path.is_file() {
+                tera.add_raw_template(&template.template, &template_path.read()?)
+                    .map_err(|error| {
+                        traceback!(TemplateError, "{template_path}: {}", Error::from(error))
+                    })?;
+            }
+        }
+        for (key, value) in self.context.iter() {
+            if !context.contains_key(key) {
+                context.insert(key, value);
+            }
+        }
+        Ok(())
+    }
+    pub fn render(&self, craft: &Craft) -> Result<Vec<(String, Vec<Option<Table>>)>> {
+        let (mut tera, context) = tera(craft)?;
+        let mut ttargets = Vec::<(String, Vec<Option<Table>>)>::new();
+        for template in self.templates.iter() {
+            if !template.applies(&context) {
+                continue;
+            }
+            if !tera.get_template_names().any(|name| name == template.template) {
+                return Err(traceback!(
+                    TemplateError,
+                    "template {:#?} is neither in the pack nor embedded",
+                    template.template
+                ));
+            }
+            let contexts = match &template.each {
+                Some(each) => match context.get(each) {
+                    Some(serde_json::Value::Array(entries)) => entries
+                        .iter()
+                        .map(|entry| {
+                            let mut context = context.clone();
+                            context.insert("entry", entry);
+                            context
+                        })
+                        .collect::<Vec<Context>>(),
+                    _ => {
+                        return Err(traceback!(
+                            TemplateError,
+                            "`each' of template {:#?} refers to {:#?} which is not an array",
+                            template.template,
+                            each
+                        ));
+                    }
+                },
+                None => vec![context.clone()],
+            };
+            for context in contexts {
+                let output = tera.render_str(&template.output, &context)?;
+                let rendered = tera.render(&template.template, &context)?;
+                let mut entry = Table::new();
+                entry.insert("name".to_string(), Value::String(template.template.clone()));
+                entry.insert("path".to_string(), Value::String(output));
+                ttargets.push((rendered, vec![Some(entry)]));
+            }
+        }
+        Ok(ttargets)
+    }
+}
+
+impl PackTemplate {
+    pub fn applies(&self, context: &Context) -> bool {
+        self.when.iter().all(|condition| match condition.strip_prefix('!') {
+            Some(name) => !truthy(context.get(name.trim())),
+            None => truthy(context.get(condition.trim())),
+        })
+    }
+}
+
+fn truthy(value: Option<&serde_json::Value>) -> bool {
+    match value {
+        None | Some(serde_json::Value::Null) => false,
+        Some(serde_json::Value::Bool(value)) => *value,
+        Some(serde_json::Value::Number(value)) => value.as_f64() != Some(0.0),
+        Some(serde_json::Value::String(value)) => !value.is_empty(),
+        Some(serde_json::Value::Array(value)) => !value.is_empty(),
+        Some(serde_json::Value::Object(value)) => !value.is_empty(),
+    }
+}
@@ -0,0 +1,254 @@
+use crate::cli::Craft;
+use crate::dependencies::add_manifest_dependency;
+use crate::errors::{Error, Result};
+use crate::workspace::{
+    add_workspace_member, hoist_workspace_dependencies, read_manifest_document,
+};
+use clap::ValueEnum;
+use iocore::Path;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// `Step` is a single side effect of generating a crate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum Step {
+    RemoveDirectory {
+        path: Path,
+    },
+    WriteFile {
+        path: Path,
+        contents: String,
+    },
+    AddWorkspaceMember {
+        workspace_manifest: Path,
+        member: String,
+    },
+    HoistDependencies {
+        manifest: Path,
+        workspace_manifest: Path,
+    },
+    AddDependency {
+        manifest: Path,
+        target: Option<String>,
+        table: String,
+        name: String,
+        entry: String,
+    },
+    ShellCommand {
+        command: String,
+        current_dir: Path,
+    },
+    CargoSubcommand {
+        subcommand: String,
+        command: String,
+        current_dir: Path,
+    },
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Step::RemoveDirectory { path } => write!(f, "remove {path}"),
+            Step::WriteFile { path, contents } => {
+                write!(f, "write {path} ({} bytes)", contents.len())
+            }
+            Step::AddWorkspaceMember {
+                workspace_manifest,
+                member,
+            } => write!(f, "add {member:#?} to the members of {workspace_manifest}"),
+            Step::HoistDependencies {
+                manifest,
+                workspace_manifest,
+            } => write!(
+                f,
+                "hoist dependencies of {manifest} into {workspace_manifest}"
+            ),
+            Step::AddDependency {
+                manifest,
+                target,
+                table,
+                name,
+                entry,
+            } => match target {
+                Some(target) => write!(
+                    f,
+                    "add {name} = {entry} to [target.'{target}'.{table}] of {manifest}"
+                ),
+                None => write!(f, "add {name} = {entry} to [{table}] of {manifest}"),
+            },
+            Step::ShellCommand {
+                command,
+                current_dir,
+            }
+            | Step::CargoSubcommand {
+                command,
+                current_dir,
+                ..
+            } => write!(f, "run `{command}' in {current_dir}"),
+        }
+    }
+}
+
+impl Step {
+    pub fn execute(&self, craft: &Craft) -> Result<()> {
+        match self {
+            Step::RemoveDirectory { path } => {
+                craft.sink.remove(path)?;
+            }
+            Step::WriteFile { path, contents } => {
+                craft.sink.write(path, contents)?;
+                if !craft.silent && craft.verbose {
+                    eprintln!("wrote {path}");
+                }
+            }
+            Step::AddWorkspaceMember {
+                workspace_manifest,
+                member,
+            } => {
+                let mut workspace = read_manifest_document(&*craft.sink, workspace_manifest)?;
+                if add_workspace_member(&mut workspace, member)? {
+                    craft.sink.write(workspace_manifest, &workspace.to_string())?;
+                    if !craft.silent && craft.verbose {
+                        eprintln!("added {member} to {workspace_manifest}");
+                    }
+                }
+            }
+            Step::HoistDependencies {
+                manifest,
+                workspace_manifest,
+            } => {
+                let kept =
+                    hoist_workspace_dependencies(&*craft.sink, manifest, workspace_manifest)?;
+                if !craft.silent {
+                    for name in kept {
+                        eprintln!(
+                            "kept {name} in {manifest}: its `default-features' differs from {workspace_manifest}"
+                        );
+                    }
+                }
+                if !craft.silent && craft.verbose {
+                    eprintln!("hoisted dependencies of {manifest} into {workspace_manifest}");
+                }
+            }
+            Step::AddDependency {
+                manifest,
+                target,
+                table,
+                name,
+                entry,
+            } => {
+                let entry = entry
+                    .parse::<toml_edit::Value>()
+                    .map_err(|error| Error::DeserializationError(format!("{entry}: {error}")))?;
+                let mut document = read_manifest_document(&*craft.sink, manifest)?;
+                add_manifest_dependency(&mut document, target.as_deref(), table, name, entry)?;
+                craft.sink.write(manifest, &document.to_string())?;
+                if !craft.silent && craft.verbose {
+                    eprintln!("added {name} to {manifest}");
+                }
+            }
+            Step::ShellCommand {
+                command,
+                current_dir,
+            } => {
+                craft.shell_command(command, current_dir)?;
+            }
+            Step::CargoSubcommand { subcommand, .. } => {
+                craft.call_cargo_subcommand(subcommand)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `Plan` lists, in order, every file written and every command
+/// issued by [`Craft::go`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub steps: Vec<Step>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PlanFormat {
+    Human,
+    Json,
+}
+
+/// `VerifyStep` is a cargo subcommand run against the new crate
+/// once it has been generated
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum VerifyStep {
+    None,
+    Check,
+    Clippy,
+    Build,
+    Test,
+    Doc,
+}
+
+impl VerifyStep {
+    pub fn subcommand(&self) -> Option<&'static str> {
+        match self {
+            VerifyStep::None => None,
+            VerifyStep::Check => Some("check"),
+            VerifyStep::Clippy => Some("clippy"),
+            VerifyStep::Build => Some("build"),
+            VerifyStep::Test => Some("test"),
+            VerifyStep::Doc => Some("doc"),
+        }
+    }
+}
+
+impl Plan {
+    pub fn push(&mut self, step: Step) {
+        self.steps.push(step);
+    }
+    pub fn extend(&mut self, steps: impl IntoIterator<Item = Step>) {
+        self.steps.extend(steps);
+    }
+    pub fn written_paths(&self) -> Vec<Path> {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::WriteFile { path, .. } => Some(path.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+    pub fn commands(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::ShellCommand { command, .. } | Step::CargoSubcommand { command, .. } => {
+                    Some(command.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+    pub fn format(&self, format: PlanFormat) -> Result<String> {
+        Ok(match format {
+            PlanFormat::Human => self.to_string(),
+            PlanFormat::Json => serde_json::to_string_pretty(self)?,
+        })
+    }
+    pub fn execute(&self, craft: &Craft) -> Result<()> {
+        for step in self.steps.iter() {
+            step.execute(craft)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let count = self.steps.len().to_string();
+        for (index, step) in self.steps.iter().enumerate() {
+            let cur = (index + 1).to_string();
+            let pad = " ".repeat(count.len() - cur.len());
+            writeln!(f, "[{pad}{cur}/{count}] {step}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,360 @@
+use crate::cli::Craft;
+use crate::errors::{Error, Result};
+use iocore::Path;
+use serde::de::Error as _;
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+
+/// `RECEIPT_SCHEMA_VERSION` is written into every receipt as
+/// `schema_version` so that receipts of older layouts of [`Craft`]
+/// can be migrated when read
+pub const RECEIPT_SCHEMA_VERSION: u64 = 1;
+
+/// `RECEIPT_MIGRATIONS[n]` upgrades a receipt of schema version `n`
+/// to `n + 1`, receipts without `schema_version` being of version 0
+pub const RECEIPT_MIGRATIONS: [fn(&mut Value); RECEIPT_SCHEMA_VERSION as usize] =
+    [migrate_unversioned_receipt];
+
+pub type ReceiptParseErrors = Vec<(String, serde_json::Error)>;
+
+/// `ReceiptStore` keeps one receipt of [`Craft`] per line of the
+/// file at `path`, appending to it under an advisory lock so that
+/// concurrent runs of cargo-craft do not clobber each other's
+/// receipts. Once the file would grow beyond `max_size` bytes it is
+/// rotated to `<path>.1`, `<path>.2` and so on, keeping `keep` of
+/// them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptStore {
+    path: Path,
+    max_size: u64,
+    keep: usize,
+}
+
+impl ReceiptStore {
+    pub fn new(path: Path) -> ReceiptStore {
+        ReceiptStore {
+            path,
+            max_size: 4 * 1024 * 1024,
+            keep: 4,
+        }
+    }
+    pub fn with_rotation(mut self, max_size: u64, keep: usize) -> ReceiptStore {
+        self.max_size = max_size;
+        self.keep = keep;
+        self
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// `rotated_path` is the path of the `n`th most recently rotated file
+    pub fn rotated_path(&self, n: usize) -> Path {
+        Path::raw(format!("{}.{n}", self.path))
+    }
+    /// `append` writes `receipt` at the end of the store
+    pub fn append(&self, receipt: &Craft) -> Result<()> {
+        let line = format!("{}\n", encode_receipt(receipt)?);
+        let _lock = self.lock(true)?;
+        if self.path.is_file() {
+            let size = std::fs::metadata(self.path.to_string())?.len();
+            if (size > 0 && size + line.len() as u64 > self.max_size)
+                || self.holds_legacy_layout()?
+            {
+                self.rotate()?;
+            }
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(self.path.to_string())
+            .map_err(|error| Error::IOError(format!("error opening {}: {error}", self.path)))?;
+        // files written before the store did not end with a newline
+        let mut last = [b'\n'];
+        if file.seek(SeekFrom::End(0))? > 0 {
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+        }
+        let line = if last[0] == b'\n' {
+            line
+        } else {
+            format!("\n{line}")
+        };
+        file.write_all(line.as_bytes())
+            .map_err(|error| Error::IOError(format!("error writing {}: {error}", self.path)))?;
+        Ok(())
+    }
+    /// `read` returns every receipt of the store, oldest first, along
+    /// with the lines which could not be read as receipts
+    pub fn read(&self) -> Result<(Vec<Craft>, ReceiptParseErrors)> {
+        let mut receipts = Vec::<Craft>::new();
+        let mut errors = ReceiptParseErrors::new();
+        if !self.path.exists() && !self.rotated_path(1).exists() {
+            return Ok((receipts, errors));
+        }
+        // reading does without the lock where it cannot be taken, e.g. read-only directories
+        let _lock = self.lock(false).ok();
+        for n in (1..=self.keep).rev() {
+            read_receipts(&self.rotated_path(n), &mut receipts, &mut errors)?;
+        }
+        read_receipts(&self.path, &mut receipts, &mut errors)?;
+        Ok((receipts, errors))
+    }
+    /// `lock` holds an advisory lock on `<path>.lock` until dropped,
+    /// which outlives the rotation of the file at `path`
+    fn lock(&self, exclusive: bool) -> Result<File> {
+        let path = Path::raw(format!("{}.lock", self.path));
+        if let Some(parent) = path.parent() {
+            parent.mkdir()?;
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path.to_string())
+            .map_err(|error| Error::IOError(format!("error opening {path}: {error}")))?;
+        if exclusive {
+            file.lock()
+        } else {
+            file.lock_shared()
+        }
+        .map_err(|error| Error::IOError(format!("error locking {path}: {error}")))?;
+        Ok(file)
+    }
+    /// `holds_legacy_layout` tells whether the file at `path` holds
+    /// receipts written as a single JSON document rather than one per
+    /// line, in which case it is rotated instead of appended to
+    fn holds_legacy_layout(&self) -> Result<bool> {
+        let mut line = String::new();
+        BufReader::new(File::open(self.path.to_string())?).read_line(&mut line)?;
+        Ok(!line.trim().is_empty()
+            && !serde_json::from_str::<Value>(&line).is_ok_and(|value| value.is_object()))
+    }
+    fn rotate(&self) -> Result<()> {
+        for n in (1..=self.keep).rev() {
+            let from = if n == 1 {
+                self.path.clone()
+            } else {
+                self.rotated_path(n - 1)
+            };
+            if from.exists() {
+                std::fs::rename(from.to_string(), self.rotated_path(n).to_string())
+                    .map_err(|error| Error::IOError(format!("error rotating {from}: {error}")))?;
+            }
+        }
+        if self.keep == 0 {
+            self.path.delete()?;
+        }
+        Ok(())
+    }
+}
+
+fn read_receipts(
+    path: &Path,
+    receipts: &mut Vec<Craft>,
+    errors: &mut ReceiptParseErrors,
+) -> Result<()> {
+    if !path.is_file() {
+        return Ok(());
+    }
+    let contents = path.read()?;
+    let mut read = Vec::<Craft>::new();
+    let mut unread = ReceiptParseErrors::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(line).and_then(decode_receipt) {
+            Ok(receipt) => read.push(receipt),
+            Err(error) => unread.push((format!("{path}:{}", index + 1), error)),
+        }
+    }
+    if read.is_empty() && !unread.is_empty() {
+        // legacy layouts: the entire file holds either a receipt or a list of receipts
+        let legacy = serde_json::from_str::<Value>(&contents).and_then(|value| match value {
+            Value::Array(values) => values.into_iter().map(decode_receipt).collect(),
+            value => decode_receipt(value).map(|receipt| vec![receipt]),
+        });
+        if let Ok(legacy) = legacy {
+            read = legacy;
+            unread.clear();
+        }
+    }
+    receipts.extend(read);
+    errors.extend(unread);
+    Ok(())
+}
+
+/// `encode_receipt` serializes `receipt` into a single line of JSON
+/// tagged with [`RECEIPT_SCHEMA_VERSION`]
+pub fn encode_receipt(receipt: &Craft) -> Result<String> {
+    let mut value = serde_json::to_value(receipt)?;
+    if let Some(fields) = value.as_object_mut() {
+        fields.insert("schema_version".to_string(), RECEIPT_SCHEMA_VERSION.into());
+    }
+    Ok(serde_json::to_string(&value)?)
+}
+
+/// `decode_receipt` deserializes a receipt of any schema version up to
+/// [`RECEIPT_SCHEMA_VERSION`] through [`RECEIPT_MIGRATIONS`]
+pub fn decode_receipt(mut value: Value) -> serde_json::Result<Craft> {
+    let version = match value
+        .as_object_mut()
+        .and_then(|fields| fields.remove("schema_version"))
+    {
+        None => 0,
+        Some(version) => version.as_u64().ok_or_else(|| {
+            serde_json::Error::custom(format!("invalid schema_version {version}"))
+        })?,
+    };
+    if version > RECEIPT_SCHEMA_VERSION {
+        return Err(serde_json::Error::custom(format!(
+            "schema_version {version} is newer than {RECEIPT_SCHEMA_VERSION}, \
+             the latest known to this version of cargo-craft"
+        )));
+    }
+    for migrate in RECEIPT_MIGRATIONS[version as usize..].iter() {
+        migrate(&mut value);
+    }
+    serde_json::from_value(value)
+}
+
+/// `migrate_unversioned_receipt` fills the fields which [`Craft`] did
+/// not have before receipts were versioned, as those versions behaved
+fn migrate_unversioned_receipt(receipt: &mut Value) {
+    let Some(fields) = receipt.as_object_mut() else {
+        return;
+    };
+    for (name, value) in [
+        ("verify", Value::from(vec!["Check", "Build", "Test", "Doc"])),
+        ("workspace", Value::from(false)),
+        ("members", Value::Array(Vec::new())),
+    ] {
+        fields.entry(name).or_insert(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::test_craft::test_path;
+    use crate::{CraftBuilder, VerifyStep};
+    use std::thread;
+
+    fn test_store(name: &str) -> ReceiptStore {
+        ReceiptStore::new(test_path("receipts", name).join("craft-receipts.ldjson"))
+    }
+    fn receipt(at: &str) -> Craft {
+        CraftBuilder::new(at).build().unwrap()
+    }
+    #[test]
+    fn test_append_concurrently() -> Result<()> {
+        let store = test_store("concurrently");
+        // written before the store: no trailing newline nor schema version
+        let mut legacy = serde_json::to_value(receipt("/tmp/legacy"))?;
+        legacy.as_object_mut().unwrap().remove("verify");
+        store.path().write(legacy.to_string().as_bytes())?;
+
+        let writers = (0..8)
+            .map(|writer| {
+                let store = store.clone();
+                thread::spawn(move || -> Result<()> {
+                    for index in 0..10 {
+                        store.append(&receipt(&format!("/tmp/writer{writer}-{index}")))?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+        for writer in writers {
+            writer.join().unwrap()?;
+        }
+        let (receipts, errors) = store.read()?;
+        assert_eq!(errors.len(), 0);
+        assert_eq!(receipts.len(), 81);
+        assert_eq!(receipts[0].at, Path::new("/tmp/legacy"));
+        assert_eq!(
+            receipts[0].verify,
+            vec![
+                VerifyStep::Check,
+                VerifyStep::Build,
+                VerifyStep::Test,
+                VerifyStep::Doc
+            ]
+        );
+        for writer in 0..8 {
+            let ats = receipts
+                .iter()
+                .map(|receipt| receipt.at.to_string())
+                .filter(|at| at.starts_with(&format!("/tmp/writer{writer}-")))
+                .collect::<Vec<String>>();
+            assert_eq!(
+                ats,
+                (0..10)
+                    .map(|index| format!("/tmp/writer{writer}-{index}"))
+                    .collect::<Vec<String>>()
+            );
+        }
+        store.path().parent().unwrap().delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_rotate() -> Result<()> {
+        let store = test_store("rotate");
+        // legacy layout: a list of receipts
+        store.path().write(
+            serde_json::to_string_pretty(&vec![receipt("/tmp/first"), receipt("/tmp/second")])?
+                .as_bytes(),
+        )?;
+        let size = encode_receipt(&receipt("/tmp/receipt-0"))?.len() as u64 + 1;
+        let store = store.with_rotation(size * 5 / 2, 2);
+        for index in 0..5 {
+            store.append(&receipt(&format!("/tmp/receipt-{index}")))?;
+        }
+        let ats = |path: &Path| -> Result<Vec<String>> {
+            let mut receipts = Vec::new();
+            read_receipts(path, &mut receipts, &mut Vec::new())?;
+            Ok(receipts.iter().map(|receipt| receipt.at.name()).collect())
+        };
+        assert_eq!(ats(store.path())?, vec!["receipt-4"]);
+        assert_eq!(ats(&store.rotated_path(1))?, vec!["receipt-2", "receipt-3"]);
+        assert_eq!(ats(&store.rotated_path(2))?, vec!["receipt-0", "receipt-1"]);
+        assert!(!store.rotated_path(3).exists());
+        assert_eq!(store.read()?.0.len(), 5);
+        store.path().parent().unwrap().delete()?;
+        Ok(())
+    }
+    #[test]
+    fn test_decode_receipt_versions() -> Result<()> {
+        let craft = receipt("/tmp/versioned");
+        let encoded = encode_receipt(&craft)?;
+        let value = serde_json::from_str::<Value>(&encoded)?;
+        assert_eq!(value["schema_version"], RECEIPT_SCHEMA_VERSION);
+        assert_eq!(decode_receipt(value.clone())?, craft);
+
+        let mut unversioned = value.clone();
+        for field in [
+            "schema_version",
+            "verify",
+            "workspace",
+            "members",
+            "error_style",
+        ] {
+            unversioned.as_object_mut().unwrap().remove(field);
+        }
+        let migrated = decode_receipt(unversioned)?;
+        assert!(!migrated.workspace);
+        assert_eq!(migrated.verify.len(), 4);
+
+        let mut newer = value.clone();
+        newer["schema_version"] = (RECEIPT_SCHEMA_VERSION + 1).into();
+        assert!(decode_receipt(newer)
+            .unwrap_err()
+            .to_string()
+            .contains("is newer than"));
+        let mut invalid = value;
+        invalid["schema_version"] = "one".into();
+        assert!(decode_receipt(invalid).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::errors::{Error, Result};
+use crate::helpers::cargo_home;
+use iocore::Path;
+use semver::{Version, VersionReq};
+use std::collections::BTreeMap;
+
+/// `VersionResolver` picks the version written into the manifest for
+/// dependencies given without one, looking first at the packages of a
+/// `Cargo.lock` then at the crates unpacked in the local registry
+/// (`$CARGO_HOME/registry/src`) so that no registry index is needed
+#[derive(Debug, Clone, Default)]
+pub struct VersionResolver {
+    locked: BTreeMap<String, Vec<Version>>,
+    registry: Option<Path>,
+}
+
+impl VersionResolver {
+    pub fn new(lockfile: Option<&Path>, registry: Option<Path>) -> Result<VersionResolver> {
+        let mut locked = BTreeMap::<String, Vec<Version>>::new();
+        if let Some(lockfile) = lockfile {
+            let lock = lockfile
+                .read()?
+                .parse::<toml::Table>()
+                .map_err(|error| Error::DeserializationError(format!("{lockfile}: {error}")))?;
+            for package in lock
+                .get("package")
+                .and_then(|packages| packages.as_array())
+                .into_iter()
+                .flatten()
+            {
+                let name = package.get("name").and_then(|name| name.as_str());
+                let version = package
+                    .get("version")
+                    .and_then(|version| version.as_str())
+                    .and_then(|version| Version::parse(version).ok());
+                if let (Some(name), Some(version)) = (name, version) {
+                    locked.entry(name.to_string()).or_default().push(version);
+                }
+            }
+        }
+        Ok(VersionResolver { locked, registry })
+    }
+    /// `registry` is the directory of the crates unpacked by cargo
+    pub fn registry() -> Path {
+        cargo_home().join("registry/src")
+    }
+    /// `resolve` returns the highest version of `name` matching `req`,
+    /// leaving pre-releases out
+    pub fn resolve(&self, name: &str, req: Option<&str>) -> Option<String> {
+        let req = match req {
+            Some(req) => VersionReq::parse(req).ok()?,
+            None => VersionReq::STAR,
+        };
+        let highest = |versions: Vec<Version>| {
+            versions
+                .into_iter()
+                .filter(|version| version.pre.is_empty() && req.matches(version))
+                .max()
+        };
+        highest(self.locked.get(name).cloned().unwrap_or_default())
+            .or_else(|| highest(self.registry_versions(name)))
+            .map(|version| version.to_string())
+    }
+    fn registry_versions(&self, name: &str) -> Vec<Version> {
+        let Some(registry) = &self.registry else {
+            return Vec::new();
+        };
+        let prefix = format!("{name}-");
+        std::fs::read_dir(registry.to_string())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .flat_map(|index| std::fs::read_dir(index.path()).into_iter().flatten().flatten())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                Version::parse(file_name.strip_prefix(&prefix)?).ok()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::test_craft::test_path;
+
+    #[test]
+    fn test_resolve_versions_from_lockfile_then_registry() -> Result<()> {
+        let root = test_path("resolver", "versions");
+        let lockfile = root.join("Cargo.lock");
+        lockfile.write(
+            b"[[package]]\nname = \"serde\"\nversion = \"1.0.200\"\n\n\
+              [[package]]\nname = \"serde\"\nversion = \"0.9.15\"\n\n\
+              [[package]]\nname = \"regex\"\nversion = \"2.0.0-alpha\"\n",
+        )?;
+        let registry = root.join("registry");
+        for unpacked in ["regex-1.10.2", "regex-1.9.0", "regex-syntax-8.0.0", "serde-1.0.300"] {
+            registry.join("index.crates.io-0000").join(unpacked).mkdir()?;
+        }
+
+        let resolver = VersionResolver::new(Some(&lockfile), Some(registry))?;
+        assert_eq!(resolver.resolve("serde", None), Some("1.0.200".to_string()));
+        assert_eq!(resolver.resolve("serde", Some("^0.9")), Some("0.9.15".to_string()));
+        assert_eq!(resolver.resolve("regex", None), Some("1.10.2".to_string()));
+        assert_eq!(resolver.resolve("regex", Some("<1.10")), Some("1.9.0".to_string()));
+        assert_eq!(resolver.resolve("regex-syntax", Some("^7")), None);
+        assert_eq!(resolver.resolve("tokio", None), None);
+        root.delete()?;
+        Ok(())
+    }
+}
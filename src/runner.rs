@@ -0,0 +1,125 @@
+use crate::errors::Result;
+use iocore::Path;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// `CommandRunner` executes the shell commands issued by [`crate::Craft`]
+/// (`cargo add`, `rustfmt`, `git` and cargo subcommands) and returns
+/// their exit code
+pub trait CommandRunner: Debug + Send + Sync {
+    fn run(&self, command: &str, current_dir: &Path) -> Result<i32>;
+}
+
+/// `ShellRunner` runs commands through the system shell
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShellRunner;
+
+impl CommandRunner for ShellRunner {
+    fn run(&self, command: &str, current_dir: &Path) -> Result<i32> {
+        Ok(iocore::shell_command(command, current_dir.clone())?)
+    }
+}
+
+/// `RecordingRunner` records commands without running them and
+/// exits with 0 unless told otherwise with [`RecordingRunner::fail`]
+#[derive(Debug, Default)]
+pub struct RecordingRunner {
+    commands: Mutex<Vec<(String, Path)>>,
+    failures: Mutex<Vec<(String, i32)>>,
+}
+
+impl RecordingRunner {
+    pub fn new() -> RecordingRunner {
+        RecordingRunner::default()
+    }
+    /// `fail` makes commands starting with `prefix` exit with `exit_code`
+    pub fn fail(self, prefix: impl std::fmt::Display, exit_code: i32) -> RecordingRunner {
+        self.failures
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .push((prefix.to_string(), exit_code));
+        self
+    }
+    pub fn commands(&self) -> Vec<String> {
+        self.invocations()
+            .into_iter()
+            .map(|(command, _)| command)
+            .collect()
+    }
+    pub fn invocations(&self) -> Vec<(String, Path)> {
+        self.commands
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .clone()
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn run(&self, command: &str, current_dir: &Path) -> Result<i32> {
+        self.commands
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .push((command.to_string(), current_dir.clone()));
+        Ok(self
+            .failures
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .iter()
+            .find(|(prefix, _)| command.starts_with(prefix.as_str()))
+            .map(|(_, exit_code)| *exit_code)
+            .unwrap_or_default())
+    }
+}
+
+/// `Runner` is the [`CommandRunner`] held by [`crate::Craft`], which
+/// neither takes part in its comparison nor in its serialization
+#[derive(Clone)]
+pub struct Runner(Arc<dyn CommandRunner>);
+
+impl Runner {
+    pub fn new(runner: impl CommandRunner + 'static) -> Runner {
+        Runner(Arc::new(runner))
+    }
+    pub fn shared(runner: Arc<dyn CommandRunner>) -> Runner {
+        Runner(runner)
+    }
+}
+
+impl Default for Runner {
+    fn default() -> Runner {
+        Runner::new(ShellRunner)
+    }
+}
+
+impl Deref for Runner {
+    type Target = dyn CommandRunner;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl Debug for Runner {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:#?}", self.0)
+    }
+}
+
+impl PartialEq for Runner {
+    fn eq(&self, _other: &Runner) -> bool {
+        true
+    }
+}
+impl Eq for Runner {}
+impl PartialOrd for Runner {
+    fn partial_cmp(&self, other: &Runner) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Runner {
+    fn cmp(&self, _other: &Runner) -> Ordering {
+        Ordering::Equal
+    }
+}
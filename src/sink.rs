@@ -0,0 +1,150 @@
+use crate::errors::{Error, Result};
+use iocore::Path;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// `FileSink` receives the files generated by [`crate::Craft`]
+pub trait FileSink: Debug + Send + Sync {
+    fn write(&self, path: &Path, contents: &str) -> Result<()>;
+    fn read(&self, path: &Path) -> Result<String>;
+    /// `remove` deletes `path` and everything beneath it
+    fn remove(&self, path: &Path) -> Result<()>;
+}
+
+/// `DiskSink` writes files to the filesystem
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskSink;
+
+impl FileSink for DiskSink {
+    fn write(&self, path: &Path, contents: &str) -> Result<()> {
+        path.write(contents.as_bytes())
+            .map_err(|error| Error::IOError(format!("error writing {path}: {error}")))?;
+        Ok(())
+    }
+    fn read(&self, path: &Path) -> Result<String> {
+        Ok(path.read()?)
+    }
+    fn remove(&self, path: &Path) -> Result<()> {
+        path.delete()?;
+        Ok(())
+    }
+}
+
+/// `MemorySink` keeps the generated files in memory, reading from
+/// the filesystem only the files it does not hold (e.g. the manifest
+/// of an existing workspace). Pair it with a
+/// [`crate::RecordingRunner`] since shell commands still run against
+/// the filesystem.
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    files: Mutex<BTreeMap<String, String>>,
+}
+
+impl MemorySink {
+    pub fn new() -> MemorySink {
+        MemorySink::default()
+    }
+    /// `files` maps the absolute path of every file written to its contents
+    pub fn files(&self) -> BTreeMap<String, String> {
+        self.files
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .clone()
+    }
+}
+
+impl FileSink for MemorySink {
+    fn write(&self, path: &Path, contents: &str) -> Result<()> {
+        self.files
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .insert(absolute(path), contents.to_string());
+        Ok(())
+    }
+    fn read(&self, path: &Path) -> Result<String> {
+        match self
+            .files
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .get(&absolute(path))
+        {
+            Some(contents) => Ok(contents.clone()),
+            None => Ok(path.read()?),
+        }
+    }
+    fn remove(&self, path: &Path) -> Result<()> {
+        let path = absolute(path);
+        let prefix = format!("{path}/");
+        self.files
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .retain(|file, _| *file != path && !file.starts_with(&prefix));
+        Ok(())
+    }
+}
+
+/// `absolute` keys files by absolute path regardless of `./` prefixes
+fn absolute(path: &Path) -> String {
+    match std::path::absolute(path.to_string()) {
+        Ok(absolute) => absolute
+            .components()
+            .collect::<std::path::PathBuf>()
+            .display()
+            .to_string(),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// `Sink` is the [`FileSink`] held by [`crate::Craft`], which neither
+/// takes part in its comparison nor in its serialization
+#[derive(Clone)]
+pub struct Sink(Arc<dyn FileSink>);
+
+impl Sink {
+    pub fn new(sink: impl FileSink + 'static) -> Sink {
+        Sink(Arc::new(sink))
+    }
+    pub fn shared(sink: Arc<dyn FileSink>) -> Sink {
+        Sink(sink)
+    }
+}
+
+impl Default for Sink {
+    fn default() -> Sink {
+        Sink::new(DiskSink)
+    }
+}
+
+impl Deref for Sink {
+    type Target = dyn FileSink;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl Debug for Sink {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:#?}", self.0)
+    }
+}
+
+impl PartialEq for Sink {
+    fn eq(&self, _other: &Sink) -> bool {
+        true
+    }
+}
+impl Eq for Sink {}
+impl PartialOrd for Sink {
+    fn partial_cmp(&self, other: &Sink) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Sink {
+    fn cmp(&self, _other: &Sink) -> Ordering {
+        Ordering::Equal
+    }
+}
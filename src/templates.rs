@@ -1,53 +1,153 @@
 use crate::cli::Craft;
-use crate::Result;
+use crate::{traceback, Error, Result};
 use crate::helpers::to_pascal_case;
+use crate::pack::TemplatePack;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
 use tera::{Context, Tera};
 use toml::{Table, Value};
 
-pub fn tera_info(craft: &Craft) -> Result<(Tera, Context)> {
-    let mut tera = Tera::default();
-    tera.add_raw_template("errors.rs", include_str!("./templates/errors.rs.tera"))?;
-    tera.add_raw_template(
-        "bare.main.rs",
-        include_str!("./templates/bare.main.rs.tera"),
-    )?;
-    tera.add_raw_template(
+/// `EMBEDDED_TEMPLATES` lists the `(template name, file name, source)`
+/// of every template built into cargo-craft. A file with the same
+/// file name in the `--templates` directory overrides the source.
+pub const EMBEDDED_TEMPLATES: [(&str, &str, &str); 17] = [
+    ("errors.rs", "errors.rs.tera", include_str!("./templates/errors.rs.tera")),
+    (
+        "errors.sources.rs",
+        "errors.sources.rs.tera",
+        include_str!("./templates/errors.sources.rs.tera"),
+    ),
+    (
+        "errors.thiserror.rs",
+        "errors.thiserror.rs.tera",
+        include_str!("./templates/errors.thiserror.rs.tera"),
+    ),
+    (
+        "errors.snafu.rs",
+        "errors.snafu.rs.tera",
+        include_str!("./templates/errors.snafu.rs.tera"),
+    ),
+    (
+        "errors.anyhow.rs",
+        "errors.anyhow.rs.tera",
+        include_str!("./templates/errors.anyhow.rs.tera"),
+    ),
+    ("bare.main.rs", "bare.main.rs.tera", include_str!("./templates/bare.main.rs.tera")),
+    (
         "bare.mod.cli.rs",
+        "bare.mod.cli.rs.tera",
         include_str!("./templates/bare.mod.cli.rs.tera"),
-    )?;
-    tera.add_raw_template("lib.rs", include_str!("./templates/lib.rs.tera"))?;
-    tera.add_raw_template("dispatch.rs", include_str!("./templates/lib_dispatch.rs.tera"))?;
-    tera.add_raw_template(
+    ),
+    ("lib.rs", "lib.rs.tera", include_str!("./templates/lib.rs.tera")),
+    ("dispatch.rs", "lib_dispatch.rs.tera", include_str!("./templates/lib_dispatch.rs.tera")),
+    (
         "{{package_name}}.rs",
+        "{{package_name}}.rs.tera",
         include_str!("./templates/{{package_name}}.rs.tera"),
-    )?;
-    tera.add_raw_template("cli", include_str!("./templates/cli.rs.tera"))?;
-    tera.add_raw_template("Cargo.toml", include_str!("./templates/Cargo.toml.tera"))?;
-    tera.add_raw_template(".gitignore", include_str!("./templates/gitignore.tera"))?;
-    tera.add_raw_template(
-        ".rustfmt.toml",
-        include_str!("./templates/rustfmt.toml.tera"),
-    )?;
-    tera.add_raw_template(
+    ),
+    ("cli", "cli.rs.tera", include_str!("./templates/cli.rs.tera")),
+    ("Cargo.toml", "Cargo.toml.tera", include_str!("./templates/Cargo.toml.tera")),
+    (
+        "Cargo.workspace.toml",
+        "Cargo.workspace.toml.tera",
+        include_str!("./templates/Cargo.workspace.toml.tera"),
+    ),
+    (".gitignore", "gitignore.tera", include_str!("./templates/gitignore.tera")),
+    (".rustfmt.toml", "rustfmt.toml.tera", include_str!("./templates/rustfmt.toml.tera")),
+    (
         "rust-toolchain.toml",
+        "rust-toolchain.toml.tera",
         include_str!("./templates/rust-toolchain.toml.tera"),
-    )?;
-    tera.add_raw_template("README.md", include_str!("./templates/README.md.tera"))?;
+    ),
+    ("README.md", "README.md.tera", include_str!("./templates/README.md.tera")),
+];
+
+/// `ErrorStyle` selects the template of the generated `errors.rs`
+#[derive(
+    ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorStyle {
+    #[default]
+    Handrolled,
+    Thiserror,
+    Snafu,
+    AnyhowApp,
+}
+
+impl ErrorStyle {
+    pub fn template_name(&self) -> &'static str {
+        match self {
+            ErrorStyle::Handrolled => "errors.rs",
+            ErrorStyle::Thiserror => "errors.thiserror.rs",
+            ErrorStyle::Snafu => "errors.snafu.rs",
+            ErrorStyle::AnyhowApp => "errors.anyhow.rs",
+        }
+    }
+    /// `dependency` is the crate the generated `errors.rs` relies on
+    pub fn dependency(&self) -> Option<&'static str> {
+        match self {
+            ErrorStyle::Handrolled => None,
+            ErrorStyle::Thiserror => Some("thiserror"),
+            ErrorStyle::Snafu => Some("snafu"),
+            ErrorStyle::AnyhowApp => Some("anyhow"),
+        }
+    }
+}
+
+impl Display for ErrorStyle {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => write!(f, "{}", value.get_name()),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn tera_info(craft: &Craft) -> Result<(Tera, Context)> {
+    let mut tera = Tera::default();
+    for (name, filename, source) in EMBEDDED_TEMPLATES {
+        match craft.templates.as_ref().map(|templates| templates.join(filename)) {
+            Some(path) if path.is_file() => {
+                let source = path.read()?;
+                tera.add_raw_template(name, &source)
+                    .map_err(|error| traceback!(TemplateError, "{path}: {}", Error::from(error)))?;
+            }
+            _ => tera.add_raw_template(name, source)?,
+        }
+    }
 
     let mut context = Context::new();
-    context.insert("crate_name", &craft.crate_name());
-    context.insert("is_cargo_command", &craft.crate_name().starts_with("cargo-"));
+    let crate_name = craft.crate_name()?;
+    context.insert("crate_name", &crate_name);
+    context.insert("is_cargo_command", &crate_name.starts_with("cargo-"));
     context.insert("crate_version", &craft.version());
-    context.insert("package_name", &craft.package_name());
+    context.insert("package_name", &craft.package_name()?);
     context.insert(
         "package_description",
         &craft.description.clone().unwrap_or_default(),
     );
-    context.insert("struct_name", &craft.struct_name());
+    context.insert("struct_name", &craft.struct_name()?);
     context.insert("craft_lib", &true);
     context.insert("craft_cli", &craft.is_cli());
+    context.insert("craft_iocore", &!craft.no_iocore);
+    context.insert("craft_serde", &!craft.no_serde);
+    context.insert("craft_error_style", &craft.error_style.to_string());
+    context.insert("craft_error_sources", &craft.error_sources);
     context.insert("crate_path", &craft.path());
-    context.insert("lib_path", &craft.lib_path());
+    context.insert("lib_path", &craft.lib_path()?);
+    context.insert("craft_workspace", &craft.workspace);
+    context.insert(
+        "workspace_members",
+        &if craft.workspace {
+            craft.members()?
+        } else {
+            Vec::new()
+        },
+    );
+    context.insert("workspace_member", &craft.is_workspace_member());
+    context.insert("workspace_inherits", &craft.workspace_inherits());
     Ok((tera, context))
 }
 
@@ -61,14 +161,14 @@ pub fn tera(craft: &Craft) -> Result<(Tera, Context)> {
             case_variants.insert("name".to_string(), Value::String(name.to_string()));
             case_variants.insert("lowercase".to_string(), Value::String(name.to_lowercase().to_string()));
             case_variants.insert("uppercase".to_string(), Value::String(name.to_uppercase().to_string()));
-            case_variants.insert("pascalcase".to_string(), Value::String(to_pascal_case(&name)));
+            case_variants.insert("pascalcase".to_string(), Value::String(to_pascal_case(name)));
             case_variants
         })
         .collect::<Vec<Table>>();
-    context.insert("crate_binaries", &craft.bin_entries());
-    context.insert("crate_lib", &craft.lib_entry("lib.rs"));
+    context.insert("crate_binaries", &craft.bin_entries()?);
+    context.insert("crate_lib", &craft.lib_entry("lib.rs")?);
     context.insert("craft_value_enum", &(craft.is_cli() && craft.value_enum));
-    context.insert("craft_subcommands", &(subcommands.len() > 0));
+    context.insert("craft_subcommands", &(!subcommands.is_empty()));
     context.insert("subcommands", &subcommands);
     context.insert(
         "craft_dependencies",
@@ -79,6 +179,26 @@ pub fn tera(craft: &Craft) -> Result<(Tera, Context)> {
             .collect::<Vec<toml::Table>>(),
     );
     context.insert("craft_errors", &craft.error_types()?);
+    context.insert("craft_exit_codes", &craft.error_exit_codes()?);
+    context.insert(
+        "craft_error_conversions",
+        &craft
+            .error_conversions()?
+            .into_iter()
+            .flat_map(|(name, paths)| {
+                paths.into_iter().map(move |path| {
+                    let mut conversion = Table::new();
+                    conversion.insert("name".to_string(), Value::String(name.clone()));
+                    conversion.insert("from".to_string(), Value::String(path));
+                    conversion
+                })
+            })
+            .collect::<Vec<Table>>(),
+    );
+    let mut tera = tera;
+    if let Some(path) = &craft.pack {
+        TemplatePack::load(path)?.register(path, &mut tera, &mut context)?;
+    }
     Ok((tera, context))
 }
 pub fn render(craft: &Craft, template_name: &str) -> Result<Option<String>> {
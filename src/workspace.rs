@@ -0,0 +1,563 @@
+use crate::cli::{Craft, DEFAULT_BIN_NAME};
+use crate::errors::{Error, Result};
+use crate::plan::Step;
+use crate::sink::FileSink;
+use iocore::Path;
+use toml::Table;
+use toml_edit::{DocumentMut, InlineTable, Item, Value};
+
+/// keys of `[workspace.package]` that member crates inherit via `key.workspace = true`
+pub const WORKSPACE_INHERITABLE_KEYS: [&str; 3] = ["version", "edition", "license"];
+
+/// keys of a dependency specification that belong to `[workspace.dependencies]`
+/// rather than to the member's own dependency table
+pub const WORKSPACE_DEPENDENCY_KEYS: [&str; 8] = [
+    "version",
+    "registry",
+    "git",
+    "branch",
+    "tag",
+    "rev",
+    "package",
+    "default-features",
+];
+
+impl Craft {
+    pub fn members(&self) -> Result<Vec<String>> {
+        Ok(if self.members.is_empty() {
+            vec![self.crate_name()?]
+        } else {
+            self.members.clone()
+        })
+    }
+    /// `workspace_root` returns the root of the workspace containing
+    /// the crate at `self.at`, be it generated alongside it with
+    /// `--workspace' or pre-existing with `--into-workspace'
+    pub fn workspace_root(&self) -> Option<Path> {
+        self.workspace_root
+            .clone()
+            .or_else(|| self.into_workspace.clone())
+    }
+    pub fn is_workspace_member(&self) -> bool {
+        self.workspace_root().is_some()
+    }
+    pub fn workspace_manifest_path(&self) -> Option<Path> {
+        self.workspace_root().map(|root| root.join("Cargo.toml"))
+    }
+    /// `workspace_member_path` returns `self.at` relative to the
+    /// workspace root as listed in `workspace.members`
+    pub fn workspace_member_path(&self) -> Result<String> {
+        let root = self
+            .workspace_root()
+            .ok_or_else(|| Error::RuntimeError(format!("{} is not a workspace member", self.at)))?;
+        let relative = std::path::Path::new(&self.path().to_string())
+            .strip_prefix(root.try_canonicalize().to_string())
+            .map_err(|_| {
+                Error::RuntimeError(format!(
+                    "{} is not located under the workspace {root}",
+                    self.at
+                ))
+            })?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<String>>()
+            .join("/");
+        Ok(relative)
+    }
+    /// `member` returns the [`Craft`] of a member crate of the workspace
+    /// at `self.at`, which shares every option of the workspace but the
+    /// ones naming what a single crate contains
+    pub fn member(&self, name: &str) -> Craft {
+        let mut member = self.clone();
+        member.at = self.path_to(name);
+        member.workspace = false;
+        member.members = Vec::new();
+        member.workspace_root = Some(self.path());
+        member.package_name = None;
+        member.default_bin_name = DEFAULT_BIN_NAME.to_string();
+        member.bin = Vec::new();
+        member.subcommand_names = Vec::new();
+        member.add_error_type = Vec::new();
+        member
+    }
+    /// `workspace_inherits` lists the keys of `[workspace.package]`
+    /// that a workspace member inherits from its workspace root
+    pub fn workspace_inherits(&self) -> Vec<String> {
+        let Some(manifest_path) = self.workspace_manifest_path() else {
+            return Vec::new();
+        };
+        // a workspace generated alongside its members declares every key
+        if self.workspace_root.is_some() {
+            return WORKSPACE_INHERITABLE_KEYS
+                .iter()
+                .map(|key| key.to_string())
+                .collect();
+        }
+        // a workspace without `[workspace.package]` has nothing to inherit
+        let package = manifest_path
+            .read()
+            .ok()
+            .and_then(|manifest| manifest.parse::<Table>().ok())
+            .and_then(|manifest| {
+                manifest
+                    .get("workspace")?
+                    .get("package")?
+                    .as_table()
+                    .cloned()
+            })
+            .unwrap_or_default();
+        WORKSPACE_INHERITABLE_KEYS
+            .iter()
+            .filter(|key| package.contains_key(**key))
+            .map(|key| key.to_string())
+            .collect()
+    }
+    pub fn workspace_steps(&self) -> Result<Vec<Step>> {
+        let mut steps = self.scaffold_steps()?;
+        let members = self
+            .members()?
+            .iter()
+            .map(|name| self.member(name))
+            .collect::<Vec<Craft>>();
+        // every member must exist before cargo can load the workspace
+        for member in members.iter() {
+            steps.extend(member.scaffold_steps()?);
+        }
+        for member in members.iter() {
+            steps.extend(member.dependency_steps()?);
+            steps.extend(member.hoist_step());
+        }
+        Ok(steps)
+    }
+    /// `into_workspace_steps` registers the crate at `self.at` in
+    /// the `workspace.members` of an existing workspace before
+    /// generating it so that `cargo add` resolves the workspace
+    pub fn into_workspace_steps(&self) -> Result<Vec<Step>> {
+        let workspace_manifest = self
+            .workspace_manifest_path()
+            .ok_or_else(|| Error::RuntimeError(format!("{} is not a workspace member", self.at)))?;
+        let mut steps = vec![Step::AddWorkspaceMember {
+            workspace_manifest,
+            member: self.workspace_member_path()?,
+        }];
+        steps.extend(self.scaffold_steps()?);
+        steps.extend(self.dependency_steps()?);
+        steps.extend(self.hoist_step());
+        Ok(steps)
+    }
+    /// `hoist_step` moves the dependencies added to a member crate
+    /// into the `[workspace.dependencies]` of its workspace root
+    pub fn hoist_step(&self) -> Option<Step> {
+        Some(Step::HoistDependencies {
+            manifest: self.manifest_path(),
+            workspace_manifest: self.workspace_manifest_path()?,
+        })
+    }
+}
+
+/// `hoist_workspace_dependencies` applies [`hoist_dependencies`] to
+/// the manifests held by `sink`
+pub fn hoist_workspace_dependencies(
+    sink: &dyn FileSink,
+    manifest_path: &Path,
+    workspace_manifest_path: &Path,
+) -> Result<Vec<String>> {
+    let mut workspace = read_manifest_document(sink, workspace_manifest_path)?;
+    let mut manifest = read_manifest_document(sink, manifest_path)?;
+    let kept = hoist_dependencies(&mut manifest, &mut workspace)?;
+    sink.write(workspace_manifest_path, &workspace.to_string())?;
+    sink.write(manifest_path, &manifest.to_string())?;
+    Ok(kept)
+}
+
+pub fn read_manifest_document(sink: &dyn FileSink, path: &Path) -> Result<DocumentMut> {
+    sink.read(path)?
+        .parse::<DocumentMut>()
+        .map_err(|error| Error::DeserializationError(format!("{path}: {error}")))
+}
+
+/// `add_workspace_member` appends `member` to the
+/// `workspace.members` of `workspace` unless already listed, either
+/// literally or through a `dir/*` glob. Returns whether it was added.
+pub fn add_workspace_member(workspace: &mut DocumentMut, member: &str) -> Result<bool> {
+    let members = workspace
+        .get_mut("workspace")
+        .and_then(Item::as_table_like_mut)
+        .ok_or_else(|| Error::DeserializationError("missing `[workspace]' table".to_string()))?
+        .entry("members")
+        .or_insert(Item::Value(Value::Array(toml_edit::Array::new())))
+        .as_array_mut()
+        .ok_or_else(|| {
+            Error::DeserializationError("`workspace.members' is not an array".to_string())
+        })?;
+    let parent = member
+        .rsplit_once('/')
+        .map(|(parent, _)| format!("{parent}/*"))
+        .unwrap_or_else(|| "*".to_string());
+    if members
+        .iter()
+        .filter_map(|entry| entry.as_str())
+        .any(|entry| entry.trim_end_matches('/') == member || entry == parent)
+    {
+        return Ok(false);
+    }
+    // keep the layout of multi-line arrays
+    let prefix = members
+        .iter()
+        .last()
+        .and_then(|entry| entry.decor().prefix()?.as_str().map(String::from));
+    members.push(member);
+    if let (Some(prefix), Some(entry)) = (prefix, members.iter_mut().last()) {
+        entry.decor_mut().set_prefix(prefix);
+    }
+    Ok(true)
+}
+
+/// `hoist_dependencies` moves every registry or git dependency of
+/// `manifest`, target-specific ones included, into the
+/// `[workspace.dependencies]` of `workspace`, leaving
+/// `{ workspace = true }` behind. Dependencies that `workspace`
+/// already declares keep their declaration, unless it disagrees on
+/// `default-features`: those stay in `manifest` as they are and their
+/// names are returned.
+pub fn hoist_dependencies(
+    manifest: &mut DocumentMut,
+    workspace: &mut DocumentMut,
+) -> Result<Vec<String>> {
+    let workspace_dependencies = workspace
+        .entry("workspace")
+        .or_insert(toml_edit::table())
+        .as_table_mut()
+        .ok_or_else(|| Error::DeserializationError("`workspace' is not a table".to_string()))?
+        .entry("dependencies")
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or_else(|| {
+            Error::DeserializationError("`workspace.dependencies' is not a table".to_string())
+        })?;
+    let mut kept = Vec::<String>::new();
+    let mut hoist = |table: &mut Item| {
+        for kind in ["dependencies", "dev-dependencies", "build-dependencies"] {
+            let Some(dependencies) = table.get_mut(kind).and_then(Item::as_table_like_mut) else {
+                continue;
+            };
+            for (name, item) in dependencies.iter_mut() {
+                let Some((workspace_spec, member_spec)) = split_dependency(item) else {
+                    continue;
+                };
+                match workspace_dependencies.get(name.get()) {
+                    // cargo would silently apply the `default-features` of the workspace
+                    Some(declared) if default_features(declared) != default_features(item) => {
+                        kept.push(name.get().to_string());
+                        continue;
+                    }
+                    Some(_) => {}
+                    None => {
+                        workspace_dependencies.insert(name.get(), Item::Value(workspace_spec));
+                    }
+                }
+                *item = Item::Value(Value::InlineTable(member_spec));
+            }
+        }
+    };
+    hoist(manifest.as_item_mut());
+    // `[target.'cfg(..)'.dependencies]' and the like
+    if let Some(targets) = manifest.get_mut("target").and_then(Item::as_table_like_mut) {
+        for (_, target) in targets.iter_mut() {
+            hoist(target);
+        }
+    }
+    Ok(kept)
+}
+
+/// `default_features` tells whether a dependency specification
+/// enables the default features of the dependency
+fn default_features(item: &Item) -> bool {
+    item.as_table_like()
+        .and_then(|spec| {
+            spec.get("default-features")
+                .or_else(|| spec.get("default_features"))
+        })
+        .and_then(Item::as_bool)
+        .unwrap_or(true)
+}
+
+fn split_dependency(item: &Item) -> Option<(Value, InlineTable)> {
+    let mut member_spec = InlineTable::new();
+    member_spec.insert("workspace", Value::from(true));
+    if let Some(version) = item.as_str() {
+        return Some((Value::from(version), member_spec));
+    }
+    let spec = item.as_table_like()?;
+    if spec.contains_key("workspace") || spec.contains_key("path") {
+        return None;
+    }
+    let mut workspace_spec = InlineTable::new();
+    for (key, value) in spec.iter() {
+        let mut value = value.as_value()?.clone();
+        value.decor_mut().clear();
+        match key {
+            "default_features" => workspace_spec.insert("default-features", value),
+            key if WORKSPACE_DEPENDENCY_KEYS.contains(&key) => workspace_spec.insert(key, value),
+            key => member_spec.insert(key, value),
+        };
+    }
+    workspace_spec.fmt();
+    member_spec.fmt();
+    let workspace_spec = match (workspace_spec.len(), workspace_spec.get("version")) {
+        (1, Some(version)) => version.clone(),
+        _ => Value::InlineTable(workspace_spec),
+    };
+    Some((workspace_spec, member_spec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::test_craft::test_path;
+    use clap::Parser;
+    use k9::assert_equal;
+
+    #[test]
+    fn test_add_workspace_member() -> Result<()> {
+        let mut workspace = "[workspace]\nmembers = [\n    \"cli\",\n    \"plugins/*\",\n]\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+        assert_equal!(add_workspace_member(&mut workspace, "cli")?, false);
+        assert_equal!(add_workspace_member(&mut workspace, "plugins/fmt")?, false);
+        assert_equal!(add_workspace_member(&mut workspace, "crates/core")?, true);
+        assert_equal!(
+            workspace.to_string(),
+            "[workspace]\nmembers = [\n    \"cli\",\n    \"plugins/*\",\n    \"crates/core\",\n]\n"
+        );
+
+        let mut package = "[package]\nname = \"cli\"\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+        assert_equal!(add_workspace_member(&mut package, "cli").is_err(), true);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hoist_dependencies() -> Result<()> {
+        let mut workspace =
+            "[workspace]\nmembers = [\"cli\"]\n\n[workspace.dependencies]\nserde = \"1.0.100\"\n"
+                .parse::<DocumentMut>()
+                .unwrap();
+        let mut manifest = [
+            "[package]",
+            "name = \"cli\"",
+            "",
+            "[dependencies]",
+            "clap = { version = \"4.5.20\", features = [\"derive\"] }",
+            "iocore = \"3.0.3\"",
+            "serde = { version = \"1.0.213\", features = [\"derive\"] }",
+            "sibling = { path = \"../sibling\" }",
+            "",
+            "[dev-dependencies]",
+            "k9 = { version = \"0.12.0\", default-features = false }",
+            "",
+        ]
+        .join("\n")
+        .parse::<DocumentMut>()
+        .unwrap();
+
+        hoist_dependencies(&mut manifest, &mut workspace)?;
+
+        assert_equal!(
+            workspace.to_string(),
+            [
+                "[workspace]",
+                "members = [\"cli\"]",
+                "",
+                "[workspace.dependencies]",
+                "serde = \"1.0.100\"",
+                "clap = \"4.5.20\"",
+                "iocore = \"3.0.3\"",
+                "k9 = { version = \"0.12.0\", default-features = false }",
+                "",
+            ]
+            .join("\n")
+        );
+        assert_equal!(
+            manifest.to_string(),
+            [
+                "[package]",
+                "name = \"cli\"",
+                "",
+                "[dependencies]",
+                "clap = { workspace = true, features = [\"derive\"] }",
+                "iocore = { workspace = true }",
+                "serde = { workspace = true, features = [\"derive\"] }",
+                "sibling = { path = \"../sibling\" }",
+                "",
+                "[dev-dependencies]",
+                "k9 = { workspace = true }",
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_member_leaves_out_the_contents_of_the_workspace() {
+        let workspace = Craft::parse_from([
+            "cargo-craft",
+            "/tmp/members",
+            "--workspace",
+            "--member",
+            "core,cli",
+            "--cli",
+            "-D",
+            "tool",
+            "--bin",
+            "helper",
+            "--subcommands",
+            "-C",
+            "init",
+            "-e",
+            "Input",
+            "-d",
+            "regex",
+        ]);
+        let member = workspace.member("core");
+        assert_equal!(member.at, workspace.path_to("core"));
+        assert_equal!(member.workspace, false);
+        assert_equal!(member.default_bin_name, DEFAULT_BIN_NAME.to_string());
+        assert_equal!(member.bin, Vec::<String>::new());
+        assert_equal!(member.subcommand_names, Vec::<String>::new());
+        assert_equal!(member.add_error_type, Vec::<String>::new());
+        assert_equal!(member.cli, true);
+        assert_equal!(member.subcommands, true);
+        assert_equal!(member.dep, vec!["regex".to_string()]);
+    }
+    #[test]
+    fn test_hoist_target_dependencies() -> Result<()> {
+        let mut workspace = "[workspace]\nmembers = [\"cli\"]\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+        let mut manifest = [
+            "[package]",
+            "name = \"cli\"",
+            "",
+            "[target.'cfg(unix)'.dependencies]",
+            "libc = { version = \"0.2\", default-features = false }",
+            "",
+            "[target.x86_64-pc-windows-msvc.dev-dependencies]",
+            "windows-sys = \"0.59\"",
+            "",
+        ]
+        .join("\n")
+        .parse::<DocumentMut>()
+        .unwrap();
+
+        hoist_dependencies(&mut manifest, &mut workspace)?;
+
+        assert_equal!(
+            workspace.to_string(),
+            [
+                "[workspace]",
+                "members = [\"cli\"]",
+                "",
+                "[workspace.dependencies]",
+                "libc = { version = \"0.2\", default-features = false }",
+                "windows-sys = \"0.59\"",
+                "",
+            ]
+            .join("\n")
+        );
+        assert_equal!(
+            manifest.to_string(),
+            [
+                "[package]",
+                "name = \"cli\"",
+                "",
+                "[target.'cfg(unix)'.dependencies]",
+                "libc = { workspace = true }",
+                "",
+                "[target.x86_64-pc-windows-msvc.dev-dependencies]",
+                "windows-sys = { workspace = true }",
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_hoist_dependencies_disagreeing_on_default_features() -> Result<()> {
+        let workspace = [
+            "[workspace]",
+            "members = [\"cli\"]",
+            "",
+            "[workspace.dependencies]",
+            "heapless = \"0.8\"",
+            "serde = { version = \"1.0\", default-features = false }",
+            "toml = { version = \"0.8\", default-features = false }",
+            "",
+        ]
+        .join("\n");
+        let mut manifest = [
+            "[package]",
+            "name = \"cli\"",
+            "",
+            "[dependencies]",
+            "heapless = { version = \"0.8\", default-features = false }",
+            "serde = \"1.0\"",
+            "toml = { version = \"0.8\", default-features = false }",
+            "",
+        ]
+        .join("\n")
+        .parse::<DocumentMut>()
+        .unwrap();
+        let mut hoisted = workspace.parse::<DocumentMut>().unwrap();
+
+        assert_equal!(
+            hoist_dependencies(&mut manifest, &mut hoisted)?,
+            vec!["heapless".to_string(), "serde".to_string()]
+        );
+        assert_equal!(hoisted.to_string(), workspace);
+        assert_equal!(
+            manifest.to_string(),
+            [
+                "[package]",
+                "name = \"cli\"",
+                "",
+                "[dependencies]",
+                "heapless = { version = \"0.8\", default-features = false }",
+                "serde = \"1.0\"",
+                "toml = { workspace = true }",
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_workspace_inherits_keys_of_workspace_package() -> Result<()> {
+        let root = test_path("workspace", "inherits");
+        let manifest = root.join("Cargo.toml");
+        manifest.write(b"[workspace]\nmembers = []\n")?;
+        let craft = Craft::parse_from([
+            "cargo-craft".to_string(),
+            root.join("member").to_string(),
+            "--into-workspace".to_string(),
+            root.to_string(),
+        ]);
+        assert_equal!(craft.workspace_inherits(), Vec::<String>::new());
+
+        manifest.write(b"[workspace]\nmembers = []\n[workspace.package]\nedition = \"2021\"\n")?;
+        assert_equal!(craft.workspace_inherits(), vec!["edition"]);
+
+        let generated = Craft::parse_from([
+            "cargo-craft".to_string(),
+            root.join("generated").to_string(),
+            "--workspace".to_string(),
+        ]);
+        assert_equal!(
+            generated.member("core").workspace_inherits(),
+            vec!["version", "edition", "license"]
+        );
+        root.delete()?;
+        Ok(())
+    }
+}